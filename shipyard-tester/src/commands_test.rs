@@ -0,0 +1,167 @@
+use shipyard::*;
+use std::sync::Mutex;
+
+type Command = Box<dyn FnOnce(&mut AllStorages) + Send>;
+
+// 구조 변경(엔티티 생성/삭제, 컴포넌트 추가/제거)을 모아 두었다가 apply_commands_system 에서 한 번에 적용한다.
+// UniqueView 로 빌려도 넣을 수 있으므로 명령을 넣는 시스템끼리는 병렬로 실행된다.
+#[derive(Unique, Default)]
+pub(crate) struct Commands {
+    queue: Mutex<Vec<Command>>,
+}
+
+impl Commands {
+    fn push(&self, command: Command) {
+        self.queue.lock().unwrap().push(command);
+    }
+
+    pub(crate) fn spawn<C: TupleAddComponent + Send + 'static>(&self, components: C) {
+        self.push(Box::new(move |all_storages| {
+            all_storages.add_entity(components);
+        }));
+    }
+
+    pub(crate) fn delete(&self, entity: EntityId) {
+        self.push(Box::new(move |all_storages| {
+            all_storages.delete_entity(entity);
+        }));
+    }
+
+    // 적용할 때 엔티티가 이미 삭제되었으면 무시된다
+    pub(crate) fn add_component<C: TupleAddComponent + Send + 'static>(&self, entity: EntityId, components: C) {
+        self.push(Box::new(move |all_storages| {
+            all_storages.add_component(entity, components);
+        }));
+    }
+
+    pub(crate) fn remove_component<C: TupleRemove + 'static>(&self, entity: EntityId) {
+        self.push(Box::new(move |all_storages| {
+            all_storages.remove::<C>(entity);
+        }));
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn take(&self) -> Vec<Command> {
+        std::mem::take(&mut *self.queue.lock().unwrap())
+    }
+}
+
+// 한 시스템이 넣은 명령끼리는 넣은 순서대로 적용된다.
+// 병렬로 실행된 시스템들의 명령은 큐에 먼저 넣은 쪽이 먼저 적용되므로 시스템 사이의 순서에 기대면 안 된다.
+// 워크로드의 마지막에 둔다.
+pub(crate) fn apply_commands_system(mut all_storages: AllStoragesViewMut) {
+    let commands = match all_storages.borrow::<UniqueView<Commands>>() {
+        Ok(commands) => commands.take(),
+        Err(_) => return,
+    };
+    for command in commands {
+        command(&mut all_storages);
+    }
+}
+
+// workload 의 모든 시스템이 끝난 뒤 Commands 를 적용한다
+pub(crate) fn with_commands(workload: Workload) -> Workload {
+    workload.with_system(apply_commands_system)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::commands_test::*;
+
+    fn spawn_system(v_vel: View<Vel>, commands: UniqueView<Commands>) {
+        for (id, vel) in v_vel.iter().with_id() {
+            if vel.0 > 0 {
+                commands.spawn(Vel::new(vel.0 - 1, 0));
+                commands.add_component(id, Pos::new(0, 0));
+            }
+        }
+    }
+
+    fn strip_system(v_pos: View<Pos>, commands: UniqueView<Commands>) {
+        for (id, pos) in v_pos.iter().with_id() {
+            if pos.0 > 0 {
+                commands.remove_component::<Pos>(id);
+                commands.delete(id);
+            }
+        }
+    }
+
+    fn commands_workload() -> Workload {
+        with_commands((spawn_system, strip_system).into_workload())
+    }
+
+    fn decrease_vel_system(mut vm_vel: ViewMut<Vel>) {
+        for vel in (&mut vm_vel).iter() {
+            vel.0 -= 1;
+        }
+    }
+
+    // Dead 를 붙이고 AllStoragesViewMut 로 지우는 대신 삭제를 Commands 에 넣는다
+    fn delete_stopped_vel_system(v_vel: View<Vel>, commands: UniqueView<Commands>) {
+        for (id, vel) in v_vel.iter().with_id() {
+            if vel.0 == 0 {
+                commands.delete(id);
+            }
+        }
+    }
+
+    fn delete_stopped_workload() -> Workload {
+        with_commands((decrease_vel_system, delete_stopped_vel_system).into_workload())
+    }
+
+    #[test]
+    fn commands_test() {
+        let mut world = World::new();
+        world.add_unique(Commands::default());
+
+        // 명령을 넣는 두 시스템은 한 배치, 적용 단계만 따로 실행된다
+        let info = commands_workload().add_to_world(&world).unwrap();
+        assert_eq!(info.batch_info.len(), 2);
+        assert!(info.batch_info[0].systems.0.is_none());
+        assert_eq!(info.batch_info[0].systems.1.len(), 2);
+        let a = world.add_entity(Vel::new(2, 0));
+        let b = world.add_entity((Vel::new(0, 0), Pos::new(1, 1)));
+
+        world.run_workload(info.name).unwrap();
+        world.run(|entities: EntitiesView, v_vel: View<Vel>, v_pos: View<Pos>, commands: UniqueView<Commands>| {
+            assert!(commands.is_empty());
+            assert!(!entities.is_alive(b));
+            assert_eq!(v_pos[a].0, 0);
+            assert_eq!(v_vel.iter().map(|vel| vel.0).collect::<Vec<_>>(), vec![2, 1]);
+        });
+
+        // 시스템 안에서 넣은 명령은 적용 전까지 보이지 않는다
+        world.run(|commands: UniqueView<Commands>| {
+            commands.delete(a);
+            assert_eq!(commands.len(), 1);
+        });
+        assert!(world.run(|entities: EntitiesView| entities.is_alive(a)));
+        world.run(apply_commands_system);
+        assert!(!world.run(|entities: EntitiesView| entities.is_alive(a)));
+    }
+
+    #[test]
+    fn delete_stopped_test() {
+        let mut world = World::new();
+        world.add_unique(Commands::default());
+        world.add_entity(Vel::new(3, 0));
+        world.add_entity(Vel::new(2, 0));
+        world.add_entity(Vel::new(1, 0));
+        world.add_workload(delete_stopped_workload);
+
+        for alive in [2, 1, 0] {
+            world.run_workload(delete_stopped_workload).unwrap();
+            world.run(|entities: EntitiesView| {
+                assert_eq!(entities.iter().count(), alive);
+            });
+        }
+    }
+}
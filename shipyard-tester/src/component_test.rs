@@ -6,7 +6,7 @@ mod tests {
     fn add_component_test() {
         let mut world = World::new();
         world.add_entity(Pos::new(3, 3));
-        world.add_entity((Pos::new(5, 5), Vel::new(10)));
+        world.add_entity((Pos::new(5, 5), Vel::new(10, 0)));
         world.run(
             |mut entities: EntitiesViewMut, mut view_pos: ViewMut<Pos>, mut view_vel: ViewMut<Vel>| {
                 let _single_component =
@@ -16,7 +16,7 @@ mod tests {
                 let _multiple_components =
                     entities.add_entity(
                         (&mut view_pos, &mut view_vel),
-                        (Pos::new(5, 5), Vel::new(10)));
+                        (Pos::new(5, 5), Vel::new(10, 0)));
             },
         );
         world.run(
@@ -31,7 +31,7 @@ mod tests {
     fn remove_component_test() {
         let mut world = World::new();
         let entity_id =
-            world.add_entity((Pos::new(5, 5), Vel::new(10)));
+            world.add_entity((Pos::new(5, 5), Vel::new(10, 0)));
 
         let vel_component: (Option<Vel>,) =
             world.remove::<Vel>(entity_id);
@@ -47,7 +47,7 @@ mod tests {
                 let entity_id =
                     entities.add_entity(
                         (&mut view_pos, &mut view_vel),
-                        (Pos::new(5, 5), Vel::new(10)));
+                        (Pos::new(5, 5), Vel::new(10, 0)));
                 let _vel_component: Option<Pos> =
                     view_pos.remove(entity_id);// not error
                 let _pos_vel_component: (Option<Pos>, Option<Vel>) =
@@ -66,25 +66,24 @@ mod tests {
     fn delete_component_test() {
         let mut world = World::new();
         let entity_id =
-            world.add_entity((Vel::new(10), Pos::new(5, 5)));
-        let not_ret_1 = world.delete_component::<Vel>(entity_id);
-        let not_ret_2 = world.delete_component::<(Pos, Vel)>(entity_id);
-        assert_eq!(not_ret_1, ());
-        assert_eq!(not_ret_2, ());
+            world.add_entity((Vel::new(10, 0), Pos::new(5, 5)));
+        // 반환값이 없다
+        world.delete_component::<Vel>(entity_id);
+        world.delete_component::<(Pos, Vel)>(entity_id);
 
         world.run(
             |mut entities: EntitiesViewMut, mut view_pos: ViewMut<Pos>, mut view_vel: ViewMut<Vel>| {
                 let entity_id =
                     entities.add_entity(
                         (&mut view_pos, &mut view_vel),
-                        (Pos::new(5, 5), Vel::new(10)));
+                        (Pos::new(5, 5), Vel::new(10, 0)));
                 view_pos.delete(entity_id);// not error
                 (&mut view_pos, &mut view_vel).delete(entity_id);
             },
         );
 
         let entity_id =
-            world.add_entity((Vel::new(10), Pos::new(5, 5)));
+            world.add_entity((Vel::new(10, 0), Pos::new(5, 5)));
         world.strip(entity_id);
     }
 
@@ -92,13 +91,13 @@ mod tests {
     fn strip_component_test() {
         let mut world = World::new();
         let entity_id =
-            world.add_entity((Vel::new(10), Pos::new(5, 5)));
+            world.add_entity((Vel::new(10, 0), Pos::new(5, 5)));
 
         world.strip(entity_id);
 
         world.run(|mut all_storages: AllStoragesViewMut| {
             let id = all_storages.add_entity(
-                (Vel::new(10), Pos::new(5, 5))
+                (Vel::new(10, 0), Pos::new(5, 5))
             );
             all_storages.strip(id);
         });
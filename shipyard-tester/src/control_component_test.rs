@@ -6,13 +6,13 @@ mod tests {
     fn get_and_modify_component_test() {
         let mut world = World::new();
         let entity_id =
-            world.add_entity((Vel::new(0), Pos::new(0, 0)));
+            world.add_entity((Vel::new(0, 0), Pos::new(0, 0)));
 
         world.run(
             |mut view_pos: ViewMut<Pos>, mut view_vel: ViewMut<Vel>| {
                 (&mut view_vel).get(entity_id).unwrap().0 += 1;
 
-                let (pos, vel) = (&mut view_pos, &mut view_vel).get(entity_id).unwrap();
+                let (mut pos, vel) = (&mut view_pos, &mut view_vel).get(entity_id).unwrap();
                 pos.0 += 1;
                 pos.1 += 1;
                 vel.0 += 1;
@@ -33,23 +33,19 @@ mod tests {
     #[test]
     fn iterator_test() {
         let mut world = World::new();
-        world.add_entity((Vel::new(0), Pos::new(0, 0)));
-        world.add_entity((Vel::new(1), Pos::new(1, 1)));
-        world.add_entity((Vel::new(2), Pos::new(2, 2)));
+        world.add_entity((Vel::new(0, 0), Pos::new(0, 0)));
+        world.add_entity((Vel::new(1, 0), Pos::new(1, 1)));
+        world.add_entity((Vel::new(2, 0), Pos::new(2, 2)));
         world.run(
             |view_pos: View<Pos>, view_vel: View<Vel>| {
-                let mut i = 0;
-                for vel in view_vel.iter() {
-                    assert_eq!(vel.0, i);
-                    i += 1;
+                for (i, vel) in view_vel.iter().enumerate() {
+                    assert_eq!(vel.0, i as i32);
                 }
 
-                let mut i = 0;
-                for (pos, vel) in (&view_pos, &view_vel).iter() {
-                    assert_eq!(pos.0, i);
-                    assert_eq!(pos.1, i);
-                    assert_eq!(vel.0, i);
-                    i += 1;
+                for (i, (pos, vel)) in (&view_pos, &view_vel).iter().enumerate() {
+                    assert_eq!(pos.0, i as u32);
+                    assert_eq!(pos.1, i as u32);
+                    assert_eq!(vel.0, i as i32);
                 }
                 // - with id iterator
                 // iter().with_id()
@@ -7,7 +7,7 @@ mod tests {
         let mut world = World::new();
         let _empty_entity = world.add_entity(());
         let _single_component = world.add_entity(Pos::new(3, 3));
-        let _multiple_components = world.add_entity((Pos::new(5, 5), Vel::new(10)));
+        let _multiple_components = world.add_entity((Pos::new(5, 5), Vel::new(10, 0)));
         world.run(
             |mut entities: EntitiesViewMut, mut view_pos: ViewMut<Pos>, mut view_vel: ViewMut<Vel>| {
                 let _empty_entity = entities.add_entity(
@@ -20,7 +20,7 @@ mod tests {
                 let _multiple_components =
                     entities.add_entity(
                         (&mut view_pos, &mut view_vel),
-                        (Pos::new(5, 5), Vel::new(10)));
+                        (Pos::new(5, 5), Vel::new(10, 0)));
             },
         );
         world.run(
@@ -34,7 +34,7 @@ mod tests {
         let mut world = World::new();
         let _empty_entity = world.add_entity(());
         let _single_component = world.add_entity(Pos::new(3, 3));
-        let _multiple_components = world.add_entity((Pos::new(5, 5), Vel::new(10)));
+        let _multiple_components = world.add_entity((Pos::new(5, 5), Vel::new(10, 0)));
         let (firsts, seconds) = world
             .borrow::<(View<Pos>, View<Vel>)>()
             .unwrap();
@@ -43,7 +43,7 @@ mod tests {
     }
     #[test]
     fn add_entity_with_borrow_test() {
-        let mut world = World::new();
+        let world = World::new();
         let (mut entities, mut view_pos, mut view_vel) = world
             .borrow::<(EntitiesViewMut, ViewMut<Pos>, ViewMut<Vel>)>()
             .unwrap();
@@ -56,7 +56,7 @@ mod tests {
             Pos::new(3, 3));
         entities.add_entity(
             (&mut view_pos, &mut view_vel),
-            (Pos::new(5, 5), Vel::new(10)));
+            (Pos::new(5, 5), Vel::new(10, 0)));
 
         assert_eq!((&mut view_pos, &mut view_vel).iter().count(), 1);
     }
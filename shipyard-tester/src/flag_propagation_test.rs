@@ -0,0 +1,207 @@
+use crate::hierarchy::{Child, HierarchyIter, Parent};
+use shipyard::*;
+use std::collections::{HashMap, HashSet};
+
+// 자신과 모든 자손을 비활성화한다
+#[derive(Component, Debug, Default)]
+#[track(All)]
+pub(crate) struct Disabled;
+
+// 자신과 모든 자손을 숨긴다
+#[derive(Component, Debug, Default)]
+#[track(All)]
+pub(crate) struct Hidden;
+
+// 계산된 상태. Disabled 나 계층이 바뀐 엔티티와 그 자손에만 다시 계산해서 붙인다.
+// 자신이나 조상 중에 Disabled 가 있으면 EffectivelyDisabled, 없으면 EffectivelyEnabled 이다.
+// 계층에 속한 적도 Disabled 를 가진 적도 없는 엔티티에는 둘 다 없으므로,
+// 그런 엔티티까지 포함해 활성 엔티티를 거르려면 !&EffectivelyDisabled 를 쓴다.
+#[derive(Component, Debug, Default)]
+pub(crate) struct EffectivelyEnabled;
+
+#[derive(Component, Debug, Default)]
+pub(crate) struct EffectivelyDisabled;
+
+// 자신이나 조상 중에 Hidden 이 있으면 붙는다
+#[derive(Component, Debug, Default)]
+pub(crate) struct EffectivelyHidden;
+
+// 부모가 바뀐 엔티티와 새로 부모가 된 엔티티. 붙이기/떼어내기 모두 Child 추적으로 알 수 있다.
+fn relinked<'a>(parents: &'a View<'a, Parent>, children: &'a View<'a, Child>) -> impl Iterator<Item = EntityId> + 'a {
+    children
+        .inserted_or_modified()
+        .iter()
+        .ids()
+        .chain(children.removed_or_deleted())
+        .chain(parents.inserted().iter().ids())
+}
+
+// dirty 엔티티의 하위 트리만 다시 계산해서 (엔티티, 표시 여부) 를 전위 순서로 반환한다.
+// flagged 는 다시 계산하지 않는 엔티티의 현재 상태이다.
+fn propagate<M>(
+    entities: &EntitiesView,
+    dirty: HashSet<EntityId>,
+    markers: &View<M>,
+    flagged: impl Fn(EntityId) -> bool,
+    hierarchy: (&View<Parent>, &View<Child>),
+) -> Vec<(EntityId, bool)>
+    where
+        M: Component + Send + Sync,
+{
+    let mut dirty_roots = dirty
+        .iter()
+        .copied()
+        .filter(|id| entities.is_alive(*id))
+        .filter(|id| !hierarchy.ancestors(*id).any(|ancestor| dirty.contains(&ancestor)))
+        .collect::<Vec<_>>();
+    dirty_roots.sort();
+
+    let mut computed: HashMap<EntityId, bool> = HashMap::new();
+    let mut result = Vec::new();
+    for root in dirty_roots {
+        // 전위 순회이므로 부모가 항상 먼저 계산된다
+        for id in std::iter::once(root).chain(hierarchy.descendants(root)) {
+            let value = markers.contains(id)
+                || hierarchy.ancestors(id).next().is_some_and(|parent| {
+                    computed.get(&parent).copied().unwrap_or_else(|| flagged(parent))
+                });
+            computed.insert(id, value);
+            result.push((id, value));
+        }
+    }
+    result
+}
+
+fn disabled_propagation_system(
+    entities: EntitiesView,
+    disabled: View<Disabled>,
+    parents: View<Parent>,
+    children: View<Child>,
+    mut enabled_state: ViewMut<EffectivelyEnabled>,
+    mut disabled_state: ViewMut<EffectivelyDisabled>,
+) {
+    let dirty = disabled
+        .inserted()
+        .iter()
+        .ids()
+        .chain(disabled.removed())
+        .chain(relinked(&parents, &children))
+        .collect::<HashSet<_>>();
+
+    let states = propagate(
+        &entities,
+        dirty,
+        &disabled,
+        |id| disabled_state.contains(id),
+        (&parents, &children),
+    );
+    for (id, is_disabled) in states {
+        if is_disabled {
+            enabled_state.remove(id);
+            if !disabled_state.contains(id) {
+                entities.add_component(id, &mut disabled_state, EffectivelyDisabled);
+            }
+        } else {
+            disabled_state.remove(id);
+            if !enabled_state.contains(id) {
+                entities.add_component(id, &mut enabled_state, EffectivelyEnabled);
+            }
+        }
+    }
+}
+
+fn hidden_propagation_system(
+    entities: EntitiesView,
+    hidden: View<Hidden>,
+    parents: View<Parent>,
+    children: View<Child>,
+    mut effective: ViewMut<EffectivelyHidden>,
+) {
+    let dirty = hidden
+        .inserted()
+        .iter()
+        .ids()
+        .chain(hidden.removed())
+        .chain(relinked(&parents, &children))
+        .collect();
+
+    let states = propagate(&entities, dirty, &hidden, |id| effective.contains(id), (&parents, &children));
+    for (id, is_hidden) in states {
+        if !is_hidden {
+            effective.remove(id);
+        } else if !effective.contains(id) {
+            entities.add_component(id, &mut effective, EffectivelyHidden);
+        }
+    }
+}
+
+pub(crate) fn flag_propagation_workload() -> Workload {
+    (disabled_propagation_system, hidden_propagation_system).into_workload()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::flag_propagation_test::*;
+    use crate::hierarchy::Hierarchy;
+
+    #[test]
+    fn propagation_test() {
+        let world = World::new();
+        world.add_workload(flag_propagation_workload);
+
+        // panel ─ button ─ label, other
+        let (panel, button, label, other) = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>, mut vm_pos: ViewMut<Pos>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let panel = hierarchy.0.add_entity(&mut vm_pos, Pos::new(0, 0));
+                let button = hierarchy.attach_new(panel).unwrap();
+                let label = hierarchy.attach_new(button).unwrap();
+                let other = hierarchy.0.add_entity(&mut vm_pos, Pos::new(1, 1));
+                hierarchy.0.add_component(button, &mut vm_pos, Pos::new(2, 2));
+                (panel, button, label, other)
+            },
+        );
+
+        world.run(|entities: EntitiesViewMut, mut vm_disabled: ViewMut<Disabled>, mut vm_hidden: ViewMut<Hidden>| {
+            entities.add_component(panel, &mut vm_disabled, Disabled);
+            entities.add_component(label, &mut vm_hidden, Hidden);
+        });
+        world.run_workload(flag_propagation_workload).unwrap();
+
+        world.run(
+            |v_pos: View<Pos>,
+             v_enabled: View<EffectivelyEnabled>,
+             v_disabled: View<EffectivelyDisabled>,
+             v_hidden: View<EffectivelyHidden>| {
+                // 활성 엔티티만 거르기. other 는 계층 밖이라 계산되지 않았다.
+                assert!((&v_pos, !&v_disabled).iter().with_id().map(|(id, _)| id).eq([other].iter().cloned()));
+                assert!(!v_enabled.contains(other) && !v_disabled.contains(other));
+                assert!(!v_enabled.contains(label));
+                assert!(v_hidden.contains(label) && !v_hidden.contains(button));
+            },
+        );
+
+        // 패널 하나만 켜면 그 하위 트리만 다시 계산된다
+        world.run(|mut vm_disabled: ViewMut<Disabled>| {
+            vm_disabled.remove(panel);
+        });
+        world.run_workload(flag_propagation_workload).unwrap();
+        world.run(|v_enabled: View<EffectivelyEnabled>, v_disabled: View<EffectivelyDisabled>| {
+            assert_eq!(v_disabled.len(), 0);
+            assert_eq!(v_enabled.len(), 3);
+            assert!(!v_enabled.contains(other));
+        });
+
+        // 숨겨진 엔티티 밑으로 옮기면 함께 숨겨진다
+        world.run(|entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+            (entities, parents, children).attach(other, label).unwrap();
+        });
+        world.run_workload(flag_propagation_workload).unwrap();
+        world.run(|v_hidden: View<EffectivelyHidden>| {
+            assert!(v_hidden.contains(other));
+            assert!(!v_hidden.contains(panel));
+        });
+    }
+}
@@ -0,0 +1,362 @@
+use crate::hierarchy::{hierarchy_cleanup_system, Child, DefaultRelation, HierarchyIter, Parent};
+use shipyard::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// 평탄화된 계층의 한 항목
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FlatNode {
+    pub(crate) id: EntityId,
+    pub(crate) parent_index: Option<usize>, // 루트는 None. 항상 자신보다 앞에 있다.
+    pub(crate) depth: usize,                // 루트의 깊이는 0
+}
+
+// 모든 트리를 깊이 우선 순서로 펼친 배열.
+// 각 트리는 연속된 구간을 차지하므로 포인터를 따라가지 않고 순서대로 처리할 수 있다.
+// 바뀐 트리만 배열 끝에 다시 펼치고 이전 구간은 버린다. 버려진 항목이 살아있는 항목보다 많아지면 압축한다.
+#[derive(Unique, Default)]
+pub(crate) struct FlatHierarchy {
+    nodes: Vec<FlatNode>,
+    trees: BTreeMap<usize, (EntityId, usize)>, // 시작 위치 → (루트, 트리 크기)
+    starts: HashMap<EntityId, usize>,          // 루트 → 시작 위치
+    index: HashMap<EntityId, usize>,
+    garbage: usize, // 버려진 항목 수
+}
+
+impl FlatHierarchy {
+    pub(crate) fn node(&self, index: usize) -> &FlatNode {
+        &self.nodes[index]
+    }
+
+    // (위치, 항목). 부모가 항상 자식보다 먼저 나온다.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (usize, &FlatNode)> {
+        self.trees
+            .iter()
+            .flat_map(move |(&start, &(_, len))| (start..start + len).map(move |index| (index, &self.nodes[index])))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len() - self.garbage
+    }
+
+    // iter 가 돌려주는 위치의 상한. 위치로 인덱싱하는 배열의 크기로 사용한다.
+    pub(crate) fn capacity(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // (루트, 트리 크기) 배열 순서대로
+    pub(crate) fn roots(&self) -> impl Iterator<Item = (EntityId, usize)> + '_ {
+        self.trees.values().copied()
+    }
+
+    pub(crate) fn index_of(&self, id: EntityId) -> Option<usize> {
+        self.index.get(&id).copied()
+    }
+
+    // id 와 자손들
+    pub(crate) fn subtree(&self, id: EntityId) -> &[FlatNode] {
+        let (start, root) = match self.index_of(id).zip(self.root_of(id)) {
+            Some(found) => found,
+            None => return &[],
+        };
+        let tree_start = self.starts[&root];
+        let tree_end = tree_start + self.trees[&tree_start].1;
+        let depth = self.nodes[start].depth;
+        let len = self.nodes[start + 1..tree_end]
+            .iter()
+            .take_while(|node| node.depth > depth)
+            .count();
+        &self.nodes[start..=start + len]
+    }
+
+    fn root_of(&self, id: EntityId) -> Option<EntityId> {
+        let mut index = self.index_of(id)?;
+        while let Some(parent_index) = self.nodes[index].parent_index {
+            index = parent_index;
+        }
+        Some(self.nodes[index].id)
+    }
+
+    // dirty 로 표시된 트리만 다시 펼친다
+    fn rebuild(&mut self, dirty: &HashSet<EntityId>, parents: &View<Parent>, children: &View<Child>) {
+        let mut dirty = dirty.iter().copied().collect::<Vec<_>>();
+        dirty.sort();
+
+        for &root in &dirty {
+            self.remove_tree(root);
+        }
+        for root in dirty {
+            if parents.contains(root) && !children.contains(root) {
+                self.push_tree(root, parents, children);
+            }
+        }
+
+        if self.garbage > self.len() {
+            self.compact();
+        }
+    }
+
+    fn remove_tree(&mut self, root: EntityId) {
+        if let Some(start) = self.starts.remove(&root) {
+            let (_, len) = self.trees.remove(&start).unwrap();
+            for node in &self.nodes[start..start + len] {
+                self.index.remove(&node.id);
+            }
+            self.garbage += len;
+        }
+    }
+
+    fn push_tree(&mut self, root: EntityId, parents: &View<Parent>, children: &View<Child>) {
+        let start = self.nodes.len();
+        let len = push_tree(&mut self.nodes, root, parents, children);
+        for index in start..start + len {
+            self.index.insert(self.nodes[index].id, index);
+        }
+        self.trees.insert(start, (root, len));
+        self.starts.insert(root, start);
+    }
+
+    // 버려진 구간을 없애고 살아있는 트리를 앞으로 모은다
+    fn compact(&mut self) {
+        let mut nodes = Vec::with_capacity(self.len());
+        let mut trees = BTreeMap::new();
+        for (&start, &(root, len)) in &self.trees {
+            let new_start = nodes.len();
+            nodes.extend(self.nodes[start..start + len].iter().map(|node| FlatNode {
+                parent_index: node.parent_index.map(|index| index - start + new_start),
+                ..*node
+            }));
+            trees.insert(new_start, (root, len));
+        }
+
+        self.starts = trees.iter().map(|(&start, &(root, _))| (root, start)).collect();
+        self.index = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.id, index))
+            .collect();
+        self.nodes = nodes;
+        self.trees = trees;
+        self.garbage = 0;
+    }
+}
+
+// root 의 트리를 뒤에 붙이고 항목 수를 반환한다
+fn push_tree(nodes: &mut Vec<FlatNode>, root: EntityId, parents: &View<Parent>, children: &View<Child>) -> usize {
+    let start = nodes.len();
+    nodes.push(FlatNode {
+        id: root,
+        parent_index: None,
+        depth: 0,
+    });
+
+    // path[d] : 깊이 d 인 마지막 조상의 위치
+    let mut path = vec![start];
+    for (id, depth) in (parents, children).descendants_with_depth(root, usize::MAX) {
+        path.truncate(depth);
+        nodes.push(FlatNode {
+            id,
+            parent_index: Some(path[depth - 1]),
+            depth,
+        });
+        path.push(nodes.len() - 1);
+    }
+    nodes.len() - start
+}
+
+// Parent/Child 변경 추적으로 바뀐 트리를 찾아 스냅샷을 갱신한다.
+// 워크로드 안에서는 이 시스템이 마지막으로 실행된 뒤의 변경만 보이므로 추적 정보를 지우지 않는다.
+// 삭제는 hierarchy_cleanup_system 이 링크를 고친 뒤의 변경으로 알아내므로 그 뒤에 실행한다.
+fn flat_hierarchy_system(parents: View<Parent>, children: View<Child>, mut flat: UniqueViewMut<FlatHierarchy>) {
+    let changed = parents
+        .inserted_or_modified()
+        .iter()
+        .ids()
+        .chain(children.inserted_or_modified().iter().ids())
+        .chain(parents.removed_or_deleted())
+        .chain(children.removed_or_deleted())
+        .collect::<HashSet<_>>();
+    if changed.is_empty() {
+        return;
+    }
+
+    let mut dirty = HashSet::new();
+    for id in changed {
+        // 변경 전에 속했던 트리와 지금 속한 트리 모두 다시 펼친다
+        if let Some(root) = flat.root_of(id) {
+            dirty.insert(root);
+        }
+        if parents.contains(id) || children.contains(id) {
+            dirty.insert((&parents, &children).root_of(id));
+        }
+    }
+
+    flat.rebuild(&dirty, &parents, &children);
+}
+
+fn flat_hierarchy_workload() -> Workload {
+    flat_hierarchy_system
+        .after_all(hierarchy_cleanup_system::<DefaultRelation>)
+        .into_workload()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::flat_hierarchy_test::*;
+    use crate::hierarchy::{hierarchy_cleanup_workload, Hierarchy, OrphanPolicy};
+
+    // 스냅샷이 포인터를 따라간 결과와 같은지 확인
+    fn assert_matches_hierarchy(world: &World) {
+        world.run(|parents: View<Parent>, children: View<Child>, flat: UniqueView<FlatHierarchy>| {
+            let mut expected = Vec::new();
+            for (root, _) in flat.roots() {
+                expected.push(root);
+                expected.extend((&parents, &children).descendants(root));
+            }
+            assert!(flat.iter().map(|(_, node)| node.id).eq(expected));
+            assert_eq!(flat.len(), flat.iter().count());
+
+            for (index, node) in flat.iter() {
+                assert_eq!(node.depth, (&parents, &children).depth(node.id));
+                assert_eq!(
+                    node.parent_index.map(|parent| flat.node(parent).id),
+                    (&parents, &children).ancestors(node.id).next()
+                );
+                assert_eq!(flat.index_of(node.id), Some(index));
+            }
+        });
+    }
+
+    #[test]
+    fn snapshot_test() {
+        let world = World::new();
+        world.add_unique(FlatHierarchy::default());
+        world.add_workload(flat_hierarchy_workload);
+
+        let (root1, root2, e1, e2, e3) = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root1 = hierarchy.0.add_entity((), ());
+                let root2 = hierarchy.0.add_entity((), ());
+                let e1 = hierarchy.attach_new(root1).unwrap();
+                let e2 = hierarchy.attach_new(e1).unwrap();
+                let e3 = hierarchy.attach_new(root2).unwrap();
+                (root1, root2, e1, e2, e3)
+            },
+        );
+
+        world.run_workload(flat_hierarchy_workload).unwrap();
+        assert_matches_hierarchy(&world);
+        world.run(|flat: UniqueView<FlatHierarchy>| {
+            assert!(flat.subtree(e1).iter().map(|node| node.id).eq([e1, e2].iter().cloned()));
+            assert_eq!(flat.node(flat.index_of(e2).unwrap()).depth, 2);
+        });
+
+        // root1 트리만 바뀌고 root2 트리는 그대로 복사된다
+        let e4 = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut hierarchy = (entities, parents, children);
+                hierarchy.detach(e2);
+                hierarchy.attach_new(e1).unwrap()
+            },
+        );
+        world.run_workload(flat_hierarchy_workload).unwrap();
+        assert_matches_hierarchy(&world);
+        world.run(|flat: UniqueView<FlatHierarchy>| {
+            assert!(flat.subtree(root1).iter().map(|node| node.id).eq([root1, e1, e4].iter().cloned()));
+            assert!(flat.subtree(root2).iter().map(|node| node.id).eq([root2, e3].iter().cloned()));
+            assert!(flat.subtree(e2).is_empty());
+        });
+
+        // 트리 전체를 다른 트리 밑으로 옮기기
+        world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                (entities, parents, children).attach(root1, e3).unwrap();
+            },
+        );
+        world.run_workload(flat_hierarchy_workload).unwrap();
+        assert_matches_hierarchy(&world);
+        world.run(|flat: UniqueView<FlatHierarchy>| {
+            assert_eq!(flat.roots().collect::<Vec<_>>(), vec![(root2, 5)]);
+        });
+
+        // 버려진 항목이 쌓이면 압축된다
+        world.run(|flat: UniqueView<FlatHierarchy>| {
+            assert!(flat.capacity() <= 2 * flat.len());
+        });
+    }
+
+    // 순서를 거꾸로 넣어도 정리가 먼저 실행된다
+    fn reversed_workload() -> Workload {
+        (flat_hierarchy_workload, hierarchy_cleanup_workload::<DefaultRelation>).into_workload()
+    }
+
+    #[test]
+    fn cleanup_order_test() {
+        let mut world = World::new();
+        world.add_unique(FlatHierarchy::default());
+        world.add_unique(OrphanPolicy::Reparent);
+        world.add_workload(reversed_workload);
+
+        let (root, e1, e2) = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root = hierarchy.0.add_entity((), ());
+                let e1 = hierarchy.attach_new(root).unwrap();
+                let e2 = hierarchy.attach_new(e1).unwrap();
+                (root, e1, e2)
+            },
+        );
+        world.run_workload(reversed_workload).unwrap();
+        assert_matches_hierarchy(&world);
+
+        world.delete_entity(e1);
+        world.run_workload(reversed_workload).unwrap();
+        assert_matches_hierarchy(&world);
+        world.run(|flat: UniqueView<FlatHierarchy>, parents: View<Parent>, children: View<Child>| {
+            // e2 는 root 밑으로 옮겨진다
+            assert!(flat.subtree(root).iter().map(|node| node.id).eq([root, e2].iter().cloned()));
+            // 추적 정보는 다른 시스템을 위해 남아 있다
+            assert!(parents.inserted_or_modified().iter().count() > 0);
+            assert!(children.removed().any(|id| id == e2));
+        });
+    }
+
+    #[test]
+    fn propagation_over_array_test() {
+        let world = World::new();
+        world.add_unique(FlatHierarchy::default());
+        world.add_workload(flat_hierarchy_workload);
+
+        let (root, leaf) = world.run(
+            |entities: EntitiesViewMut,
+             parents: ViewMut<Parent>,
+             children: ViewMut<Child>,
+             mut vm_pos: ViewMut<Pos>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root = hierarchy.0.add_entity(&mut vm_pos, Pos::new(1, 1));
+                let mid = hierarchy.attach_new(root).unwrap();
+                let leaf = hierarchy.attach_new(mid).unwrap();
+                hierarchy.0.add_component(mid, &mut vm_pos, Pos::new(2, 0));
+                hierarchy.0.add_component(leaf, &mut vm_pos, Pos::new(0, 3));
+                (root, leaf)
+            },
+        );
+        world.run_workload(flat_hierarchy_workload).unwrap();
+
+        // 부모가 항상 앞에 있으므로 한 번의 순회로 누적 위치를 계산할 수 있다
+        world.run(|flat: UniqueView<FlatHierarchy>, v_pos: View<Pos>| {
+            let mut global = vec![(0, 0); flat.capacity()];
+            for (index, node) in flat.iter() {
+                let local = v_pos.get(node.id).map_or((0, 0), |pos| (pos.0, pos.1));
+                let base = node.parent_index.map_or((0, 0), |parent| global[parent]);
+                global[index] = (base.0 + local.0, base.1 + local.1);
+            }
+            assert_eq!(global[flat.index_of(root).unwrap()], (1, 1));
+            assert_eq!(global[flat.index_of(leaf).unwrap()], (3, 4));
+        });
+    }
+}
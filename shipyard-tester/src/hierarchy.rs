@@ -0,0 +1,1898 @@
+// 다른 크레이트에서 사용할 계층 API.
+// 테스트는 hierarchy_test 에 있다.
+use rayon::prelude::*;
+use shipyard::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::marker::PhantomData;
+
+// 계층 종류를 구분하는 표시 타입.
+// 관계마다 Parent<R>/Child<R> 저장소가 따로 있으므로 한 엔티티가 여러 트리에 동시에 속할 수 있다.
+pub trait Relation: Send + Sync + 'static {}
+
+// 관계를 지정하지 않은 Parent/Child 가 사용하는 기본 계층
+pub struct DefaultRelation;
+
+impl Relation for DefaultRelation {}
+
+// 부모 컴포넌트
+// 변경 추적은 삭제 정리와 평탄화 스냅샷 갱신에 사용된다
+#[derive(Component)]
+#[track(All)]
+pub struct Parent<R: Relation = DefaultRelation> {
+    pub(crate) num_children: usize, // 자식 갯수
+    pub(crate) first_child: EntityId,// 첫번째 자식 엔티티 ID
+    pub(crate) relation: PhantomData<R>,
+}
+
+// 자식 컴포넌트
+#[derive(Component)]
+#[track(All)]
+pub struct Child<R: Relation = DefaultRelation> {
+    pub(crate) parent: EntityId,
+    pub(crate) prev: EntityId, // 형제 체인을 원형으로 만들어 옵션을 피함
+    pub(crate) next: EntityId,
+    pub(crate) relation: PhantomData<R>,
+}
+
+/*
+!!! 엔티티 !!!
+부모 엔티티, 자식 엔티티라는 용어를 쓰기도 한다.
+하지만 이는 EOS가 아닌 객체 지향적인 개념이며 잘못된 표현이다.
+
+엔티티는 오직 ID이다. 부모 컴포넌트는 자식 엔티티 ID를 값으로 가지고 있는 컴포넌트일 뿐이다.
+자식 컴포넌트 역시 부모와 형제들 엔티티 ID를 가지고 있는 컴포넌트일 뿐이다.
+
+부모 엔티티, 자식 엔티티라고 표현하는 것은 개발자가 해당 ID를 부모, 자식 개념으로 바라보는 것이다.
+엔티티는 절대 클래스나 인스턴스가 아닌 단순한 ID이다.
+
+만약 표현한다면 부모, 자식으로 표현 하는 것이 맞을 것이다.
+부모를 만들기 위해 부모 컴포넌트가 필요하며 부모 엔티티 ID가 할당된다.
+*/
+
+// Hierarchy 변경 실패 원인
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyError {
+    WouldCreateCycle, // 부모가 자신의 자손인 경우
+    SelfParent,       // 자기 자신을 부모로 지정한 경우
+    DeadEntity,       // 삭제된 엔티티
+    NotAChild,        // 기준 형제가 자식이 아닌 경우
+    NotSiblings,      // 같은 부모의 자식이 아닌 경우
+    IndexOutOfBounds, // 자식 순서 범위를 벗어난 경우
+}
+
+impl fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HierarchyError::WouldCreateCycle => f.write_str("parent is a descendant of the entity"),
+            HierarchyError::SelfParent => f.write_str("entity cannot be its own parent"),
+            HierarchyError::DeadEntity => f.write_str("entity is not alive"),
+            HierarchyError::NotAChild => f.write_str("entity is not a child"),
+            HierarchyError::NotSiblings => f.write_str("entities do not share a parent"),
+            HierarchyError::IndexOutOfBounds => f.write_str("child index out of bounds"),
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {}
+
+// Hierarchy 구조 변경 이벤트
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyEvent {
+    Attached { child: EntityId, parent: EntityId },
+    Detached { child: EntityId, old_parent: EntityId },
+    Reordered { parent: EntityId }, // 자식 순서만 바뀜
+    Removed { id: EntityId },       // 계층에서 완전히 빠짐
+}
+
+// 프레임마다 시스템이 비우는 이벤트 큐. 관계마다 따로 있다.
+// (EntitiesViewMut, ViewMut<Parent<R>>, ViewMut<Child<R>>, UniqueViewMut<HierarchyEvents<R>>) 로 빌리면 기록된다.
+#[derive(Unique)]
+pub struct HierarchyEvents<R: Relation = DefaultRelation>(Vec<HierarchyEvent>, PhantomData<R>);
+
+impl<R: Relation> Default for HierarchyEvents<R> {
+    fn default() -> HierarchyEvents<R> {
+        HierarchyEvents(Vec::new(), PhantomData)
+    }
+}
+
+impl<R: Relation> HierarchyEvents<R> {
+    pub fn drain(&mut self) -> std::vec::Drain<'_, HierarchyEvent> {
+        self.0.drain(..)
+    }
+}
+
+pub trait Hierarchy {
+    // Removes the child status of an entity.
+    fn detach(&mut self, id: EntityId);
+    // Attaches an entity as a child to a given parent entity.
+    // Fails without modifying anything if the link would create a cycle.
+    fn attach(&mut self, id: EntityId, parent: EntityId) -> Result<(), HierarchyError>;
+    fn attach_new(&mut self, parent: EntityId) -> Result<EntityId, HierarchyError>;
+    fn remove(&mut self, id: EntityId);
+    fn remove_all(&mut self, id: EntityId);
+    fn sort_children_by<F>(&mut self, id: EntityId, compare: F)
+        where
+            F: FnMut(&EntityId, &EntityId) -> std::cmp::Ordering;
+    // All sorts are stable: children with equal keys keep their current order.
+    fn sort_children_by_key<K, F>(&mut self, id: EntityId, key: F)
+        where
+            K: Ord,
+            F: FnMut(&EntityId) -> K;
+    // Sorts the children of `id`, then the children of each child, and so on.
+    fn sort_descendants_by<F>(&mut self, id: EntityId, compare: F)
+        where
+            F: FnMut(&EntityId, &EntityId) -> std::cmp::Ordering;
+    // Sorts by a component value. Children without the component go last.
+    fn sort_children_by_component<'c, T, V>(&mut self, id: EntityId, components: V)
+        where
+            T: Ord + 'c,
+            V: Get<Out = &'c T> + Copy;
+    fn sort_descendants_by_component<'c, T, V>(&mut self, id: EntityId, components: V)
+        where
+            T: Ord + 'c,
+            V: Get<Out = &'c T> + Copy;
+    // Attaches an entity so that it ends up at `index` among the parent's children.
+    fn insert_child_at(&mut self, parent: EntityId, index: usize, id: EntityId) -> Result<(), HierarchyError>;
+    // Attaches an entity right before / after a given sibling.
+    fn insert_before(&mut self, sibling: EntityId, id: EntityId) -> Result<(), HierarchyError>;
+    fn insert_after(&mut self, sibling: EntityId, id: EntityId) -> Result<(), HierarchyError>;
+    // Moves the child at `from` so that it ends up at `to`.
+    fn move_child(&mut self, parent: EntityId, from: usize, to: usize) -> Result<(), HierarchyError>;
+    fn swap_siblings(&mut self, a: EntityId, b: EntityId) -> Result<(), HierarchyError>;
+    // Copies the links of `root` and its descendants under `new_parent` without any component.
+    // Returns the copy of `root` and the original → copy map. See `clone_subtree` to copy components too.
+    fn copy_links(&mut self, root: EntityId, new_parent: EntityId) -> Result<(EntityId, EntityMap), HierarchyError>;
+    // Rebuilds every sibling ring from `Child.parent` if any invariant is broken.
+    // Returns the violations found before repairing.
+    fn repair(&mut self) -> HierarchyReport;
+}
+
+// Hierarchy 가 사용하는 저장소 묶음. 어떤 관계의 트리를 바꿀지는 빌린 Parent<R>/Child<R> 로 정해진다.
+// HierarchyEvents 를 함께 빌리면 변경 이벤트가 기록된다.
+pub trait HierarchyStorages<'v> {
+    type Relation: Relation;
+
+    #[allow(clippy::type_complexity)]
+    fn storages(
+        &mut self,
+    ) -> (
+        &mut EntitiesViewMut<'v>,
+        &mut ViewMut<'v, Parent<Self::Relation>>,
+        &mut ViewMut<'v, Child<Self::Relation>>,
+    );
+    fn emit(&mut self, _event: HierarchyEvent) {}
+}
+
+impl<'v, R: Relation> HierarchyStorages<'v> for (EntitiesViewMut<'v>, ViewMut<'v, Parent<R>>, ViewMut<'v, Child<R>>) {
+    type Relation = R;
+
+    fn storages(&mut self) -> (&mut EntitiesViewMut<'v>, &mut ViewMut<'v, Parent<R>>, &mut ViewMut<'v, Child<R>>) {
+        (&mut self.0, &mut self.1, &mut self.2)
+    }
+}
+
+impl<'v, R: Relation> HierarchyStorages<'v>
+    for (EntitiesViewMut<'v>, ViewMut<'v, Parent<R>>, ViewMut<'v, Child<R>>, UniqueViewMut<'v, HierarchyEvents<R>>)
+{
+    type Relation = R;
+
+    fn storages(&mut self) -> (&mut EntitiesViewMut<'v>, &mut ViewMut<'v, Parent<R>>, &mut ViewMut<'v, Child<R>>) {
+        (&mut self.0, &mut self.1, &mut self.2)
+    }
+    fn emit(&mut self, event: HierarchyEvent) {
+        self.3.0.push(event);
+    }
+}
+
+// World 나 AllStorages 에서 빌린 저장소 묶음. HierarchyEvents<R> 가 있으면 이벤트도 기록한다.
+pub struct HierarchyViews<'v, R: Relation = DefaultRelation> {
+    entities: EntitiesViewMut<'v>,
+    parents: ViewMut<'v, Parent<R>>,
+    children: ViewMut<'v, Child<R>>,
+    events: Option<UniqueViewMut<'v, HierarchyEvents<R>>>,
+}
+
+impl<'v, R: Relation> HierarchyViews<'v, R> {
+    pub fn new(
+        entities: EntitiesViewMut<'v>,
+        parents: ViewMut<'v, Parent<R>>,
+        children: ViewMut<'v, Child<R>>,
+        events: Option<UniqueViewMut<'v, HierarchyEvents<R>>>,
+    ) -> HierarchyViews<'v, R> {
+        HierarchyViews {
+            entities,
+            parents,
+            children,
+            events,
+        }
+    }
+
+    // HierarchyIter 로 조회할 때 사용한다
+    pub fn query(&self) -> (&ViewMut<'v, Parent<R>>, &ViewMut<'v, Child<R>>) {
+        (&self.parents, &self.children)
+    }
+}
+
+impl<'v, R: Relation> HierarchyStorages<'v> for HierarchyViews<'v, R> {
+    type Relation = R;
+
+    fn storages(&mut self) -> (&mut EntitiesViewMut<'v>, &mut ViewMut<'v, Parent<R>>, &mut ViewMut<'v, Child<R>>) {
+        (&mut self.entities, &mut self.parents, &mut self.children)
+    }
+    fn emit(&mut self, event: HierarchyEvent) {
+        if let Some(events) = &mut self.events {
+            events.0.push(event);
+        }
+    }
+}
+
+impl<'v, H: HierarchyStorages<'v>> Hierarchy for H {
+    fn detach(&mut self, id: EntityId) {
+        let (
+            _,
+            parents,
+            children
+        ) = self.storages();
+
+        // 자식 컴포넌트 제거
+        if let Some(child) = children.remove(id) {
+            // 부모 컴포넌트에서 자식 갯수 1나 감소
+            let parent = &mut parents[child.parent];
+            parent.num_children -= 1;
+
+            if parent.num_children == 0 {
+                // 자식 없으면 부모 컴포넌트도 제거
+                parents.remove(child.parent);
+            } else {
+                if parent.first_child == id {
+                    // 삭제된 자식이 첫번쨰이면 다음 자식을 첫번째로 설정
+                    parent.first_child = child.next;
+                }
+                // 삭제된 자식을 제외하는 링크 결함
+                children[child.prev].next = child.next;
+                children[child.next].prev = child.prev;
+            }
+
+            self.emit(HierarchyEvent::Detached { child: id, old_parent: child.parent });
+        }
+    }
+    fn attach(&mut self, id: EntityId, parent: EntityId) -> Result<(), HierarchyError> {
+        {
+            let (
+                entities,
+                parents,
+                children
+            ) = self.storages();
+
+            if !entities.is_alive(id) || !entities.is_alive(parent) {
+                return Err(HierarchyError::DeadEntity);
+            }
+            if id == parent {
+                return Err(HierarchyError::SelfParent);
+            }
+            // 부모의 조상 중에 자신이 있으면 순환이 생긴다
+            if (&*parents, &*children).ancestors(parent).any(|ancestor| ancestor == id) {
+                return Err(HierarchyError::WouldCreateCycle);
+            }
+        }
+
+        // 기존 링크 관계 제거
+        self.detach(id);
+
+        let (
+            entities,
+            parents,
+            children
+        ) = self.storages();
+
+        if let Ok(mut p) = parents.get(parent) {
+            //부모에 자식 추가
+            p.num_children += 1;
+
+            let prev = children[p.first_child].prev;
+            let next = p.first_child;
+
+            children[prev].next = id;
+            children[next].prev = id;
+
+            entities.add_component(
+                id,
+                children,
+                Child {
+                    parent,
+                    prev,
+                    next,
+                    relation: PhantomData,
+                },
+            );
+        } else {
+            // 새로 부모 추가 + 부모에 자식 추가
+            entities.add_component(
+                id,
+                children,
+                Child {
+                    parent,
+                    prev: id,
+                    next: id,
+                    relation: PhantomData,
+                },
+            );
+            entities.add_component(
+                parent,
+                parents,
+                Parent {
+                    num_children: 1,
+                    first_child: id,
+                    relation: PhantomData,
+                },
+            );
+        }
+
+        self.emit(HierarchyEvent::Attached { child: id, parent });
+        Ok(())
+    }
+    fn attach_new(&mut self, parent: EntityId) -> Result<EntityId, HierarchyError> {
+        let (entities, _, _) = self.storages();
+        if !entities.is_alive(parent) {
+            return Err(HierarchyError::DeadEntity);
+        }
+        // 부모에 자식 생성하여 추가
+        let id = entities.add_entity((), ());
+        self.attach(id, parent)?;
+        Ok(id)
+    }
+    fn remove(&mut self, id: EntityId) {
+        // 계층에 속하지 않은 엔티티면 이벤트도 없다
+        let (_, parents, children) = self.storages();
+        let linked = parents.contains(id) || children.contains(id);
+
+        // 자식 링크 제거
+        self.detach(id);
+
+        // 형제와 형제의 자식들
+        let (_, parents, children) = self.storages();
+        let children = (&*parents, &*children).children(id).collect::<Vec<_>>();
+        for child_id in children {
+            self.detach(child_id);
+        }
+        self.storages().1.remove(id);
+
+        if linked {
+            self.emit(HierarchyEvent::Removed { id });
+        }
+    }
+    fn remove_all(&mut self, id: EntityId) {
+        let (
+            _,
+            parents,
+            children
+        ) = self.storages();
+
+        for child_id in (&*parents, &*children).children(id).collect::<Vec<_>>() {
+            self.remove_all(child_id);
+        }
+        self.remove(id);
+    }
+    fn sort_children_by<F>(&mut self, id: EntityId, compare: F)
+        where
+            F: FnMut(&EntityId, &EntityId) -> std::cmp::Ordering,
+    {
+        let (
+            _,
+            parents,
+            children_storage
+        ) = self.storages();
+
+        let mut children = (&*parents, &*children_storage)
+            .children(id)
+            .collect::<Vec<EntityId>>();
+        if children.len() > 1 {
+            children.sort_by(compare);
+            relink_children(parents, children_storage, id, &children);
+            self.emit(HierarchyEvent::Reordered { parent: id });
+        }
+    }
+    fn sort_children_by_key<K, F>(&mut self, id: EntityId, mut key: F)
+        where
+            K: Ord,
+            F: FnMut(&EntityId) -> K,
+    {
+        let (_, parents, children_storage) = self.storages();
+
+        // 키는 자식마다 한 번만 계산한다
+        let mut keyed = (&*parents, &*children_storage)
+            .children(id)
+            .map(|child| (key(&child), child))
+            .collect::<Vec<_>>();
+        if keyed.len() > 1 {
+            keyed.sort_by(|a, b| a.0.cmp(&b.0));
+            let children = keyed.into_iter().map(|(_, child)| child).collect::<Vec<_>>();
+            relink_children(parents, children_storage, id, &children);
+            self.emit(HierarchyEvent::Reordered { parent: id });
+        }
+    }
+    fn sort_descendants_by<F>(&mut self, id: EntityId, mut compare: F)
+        where
+            F: FnMut(&EntityId, &EntityId) -> std::cmp::Ordering,
+    {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            self.sort_children_by(id, &mut compare);
+            let (_, parents, children) = self.storages();
+            stack.extend((&*parents, &*children).children(id));
+        }
+    }
+    fn sort_children_by_component<'c, T, V>(&mut self, id: EntityId, components: V)
+        where
+            T: Ord + 'c,
+            V: Get<Out = &'c T> + Copy,
+    {
+        self.sort_children_by(id, |a, b| compare_components(components, *a, *b));
+    }
+    fn sort_descendants_by_component<'c, T, V>(&mut self, id: EntityId, components: V)
+        where
+            T: Ord + 'c,
+            V: Get<Out = &'c T> + Copy,
+    {
+        self.sort_descendants_by(id, |a, b| compare_components(components, *a, *b));
+    }
+    fn insert_child_at(&mut self, parent: EntityId, index: usize, id: EntityId) -> Result<(), HierarchyError> {
+        let (_, parents, children) = self.storages();
+        let already_child = (&*children).get(id).is_ok_and(|child| child.parent == parent);
+        let len = (&*parents).get(parent).map_or(0, |parent| parent.num_children) - already_child as usize;
+        if index > len {
+            return Err(HierarchyError::IndexOutOfBounds);
+        }
+        if !already_child {
+            self.attach(id, parent)?;
+        }
+
+        let (_, parents, children) = self.storages();
+        place_child_at(parents, children, parent, id, index);
+        self.emit(HierarchyEvent::Reordered { parent });
+        Ok(())
+    }
+    fn insert_before(&mut self, sibling: EntityId, id: EntityId) -> Result<(), HierarchyError> {
+        let (_, _, children) = self.storages();
+        let parent = (&*children).get(sibling).map_err(|_| HierarchyError::NotAChild)?.parent;
+        if id == sibling {
+            return Ok(());
+        }
+        if (&*children).get(id).map_or(true, |child| child.parent != parent) {
+            self.attach(id, parent)?;
+        }
+
+        let (_, parents, children) = self.storages();
+        if parents[parent].first_child == id {
+            parents[parent].first_child = children[id].next;
+        }
+        let prev = children[sibling].prev;
+        relink_after(children, id, prev);
+        if parents[parent].first_child == sibling {
+            parents[parent].first_child = id;
+        }
+        self.emit(HierarchyEvent::Reordered { parent });
+        Ok(())
+    }
+    fn insert_after(&mut self, sibling: EntityId, id: EntityId) -> Result<(), HierarchyError> {
+        let (_, _, children) = self.storages();
+        let parent = (&*children).get(sibling).map_err(|_| HierarchyError::NotAChild)?.parent;
+        if id == sibling {
+            return Ok(());
+        }
+        if (&*children).get(id).map_or(true, |child| child.parent != parent) {
+            self.attach(id, parent)?;
+        }
+
+        let (_, parents, children) = self.storages();
+        if parents[parent].first_child == id {
+            parents[parent].first_child = children[id].next;
+        }
+        relink_after(children, id, sibling);
+        self.emit(HierarchyEvent::Reordered { parent });
+        Ok(())
+    }
+    fn move_child(&mut self, parent: EntityId, from: usize, to: usize) -> Result<(), HierarchyError> {
+        let (_, parents, children) = self.storages();
+
+        let len = (&*parents).get(parent).map_or(0, |parent| parent.num_children);
+        if from >= len || to >= len {
+            return Err(HierarchyError::IndexOutOfBounds);
+        }
+        let id = (&*parents, &*children).children(parent).nth(from).unwrap();
+        place_child_at(parents, children, parent, id, to);
+        self.emit(HierarchyEvent::Reordered { parent });
+        Ok(())
+    }
+    fn swap_siblings(&mut self, a: EntityId, b: EntityId) -> Result<(), HierarchyError> {
+        let (_, parents, children) = self.storages();
+
+        let (a_parent, a_prev) = (&*children)
+            .get(a)
+            .map(|child| (child.parent, child.prev))
+            .map_err(|_| HierarchyError::NotAChild)?;
+        let (b_parent, b_prev) = (&*children)
+            .get(b)
+            .map(|child| (child.parent, child.prev))
+            .map_err(|_| HierarchyError::NotAChild)?;
+        if a_parent != b_parent {
+            return Err(HierarchyError::NotSiblings);
+        }
+        if a == b {
+            return Ok(());
+        }
+
+        if b_prev == a {
+            relink_after(children, a, b);
+        } else if a_prev == b {
+            relink_after(children, b, a);
+        } else {
+            relink_after(children, a, b_prev);
+            relink_after(children, b, a_prev);
+        }
+
+        let parent = &mut parents[a_parent];
+        if parent.first_child == a {
+            parent.first_child = b;
+        } else if parent.first_child == b {
+            parent.first_child = a;
+        }
+        self.emit(HierarchyEvent::Reordered { parent: a_parent });
+        Ok(())
+    }
+    fn copy_links(&mut self, root: EntityId, new_parent: EntityId) -> Result<(EntityId, EntityMap), HierarchyError> {
+        let (entities, parents, children) = self.storages();
+        if !entities.is_alive(root) || !entities.is_alive(new_parent) {
+            return Err(HierarchyError::DeadEntity);
+        }
+
+        // 전위 순회 순서이므로 부모가 항상 먼저 나오고 형제 순서도 유지된다
+        let sources = std::iter::once(root)
+            .chain((&*parents, &*children).descendants(root))
+            .collect::<Vec<_>>();
+        let mut map = HashMap::with_capacity(sources.len());
+        for &source in &sources {
+            map.insert(source, entities.add_entity((), ()));
+        }
+        let mut linked = Ok(());
+        for &source in &sources[1..] {
+            let parent = self.storages().2[source].parent;
+            linked = self.attach(map[&source], map[&parent]);
+            if linked.is_err() {
+                break;
+            }
+        }
+        let copy = map[&root];
+        if linked.is_ok() {
+            linked = self.attach(copy, new_parent);
+        }
+
+        // 실패하면 만든 엔티티를 링크와 함께 지운다
+        if let Err(err) = linked {
+            for &id in map.values() {
+                let (entities, parents, children) = self.storages();
+                parents.remove(id);
+                children.remove(id);
+                entities.delete_unchecked(id);
+                self.emit(HierarchyEvent::Removed { id });
+            }
+            return Err(err);
+        }
+        Ok((copy, EntityMap(map)))
+    }
+    fn repair(&mut self) -> HierarchyReport {
+        let (
+            entities,
+            parents,
+            children
+        ) = self.storages();
+
+        let parent_ids = (&*parents).iter().with_id().map(|(id, _)| id).collect::<Vec<_>>();
+        let mut child_ids = (&*children).iter().with_id().map(|(id, _)| id).collect::<Vec<_>>();
+
+        let report = collect_violations(&parent_ids, &child_ids, &*parents, &*children);
+        if report.is_valid() {
+            return report;
+        }
+
+        // 순환은 한 엔티티를 루트로 만들어 끊는다
+        let mut cycle_broken = false;
+        for violation in report.violations() {
+            if let HierarchyViolation::Cycle { entity } = *violation {
+                children.remove(entity);
+                cycle_broken = true;
+            }
+        }
+        if cycle_broken {
+            child_ids = (&*children).iter().with_id().map(|(id, _)| id).collect::<Vec<_>>();
+        }
+
+        // 부모별 자식 목록 (기존 링 순서를 최대한 유지)
+        let mut groups: Vec<(EntityId, Vec<EntityId>)> = Vec::new();
+        {
+            let (parents, children) = (&*parents, &*children);
+            let mut group_index: HashMap<EntityId, usize> = HashMap::new();
+            let mut grouped: HashSet<EntityId> = HashSet::new();
+            let mut push = |parent: EntityId, child: EntityId| {
+                let index = *group_index.entry(parent).or_insert_with(|| {
+                    groups.push((parent, Vec::new()));
+                    groups.len() - 1
+                });
+                groups[index].1.push(child);
+            };
+
+            // 기존 링에서 올바른 부분을 먼저 따라간다
+            for &parent_id in &parent_ids {
+                let mut cursor = parents[parent_id].first_child;
+                for _ in 0..child_ids.len() {
+                    match children.get(cursor) {
+                        Ok(child) if child.parent == parent_id && grouped.insert(cursor) => {
+                            push(parent_id, cursor);
+                            cursor = child.next;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            // 링에서 빠진 자식은 뒤에 붙인다
+            for &child_id in &child_ids {
+                if grouped.insert(child_id) {
+                    push(children[child_id].parent, child_id);
+                }
+            }
+        }
+
+        for parent_id in parent_ids {
+            parents.remove(parent_id);
+        }
+        let mut rebuilt = Vec::with_capacity(groups.len());
+        for (parent_id, mut members) in groups {
+            if let Some(index) = members.iter().position(|&member| member == parent_id) {
+                // 자기 자신이 부모인 자식
+                members.remove(index);
+                children.remove(parent_id);
+            }
+            if members.is_empty() {
+                continue;
+            }
+            if !entities.is_alive(parent_id) {
+                // 부모가 없으면 루트가 된다
+                for member in members {
+                    children.remove(member);
+                }
+                continue;
+            }
+
+            let len = members.len();
+            for (i, &member) in members.iter().enumerate() {
+                let child = &mut children[member];
+                child.prev = members[(i + len - 1) % len];
+                child.next = members[(i + 1) % len];
+            }
+            entities.add_component(
+                parent_id,
+                &mut *parents,
+                Parent {
+                    num_children: len,
+                    first_child: members[0],
+                    relation: PhantomData,
+                },
+            );
+            rebuilt.push(parent_id);
+        }
+
+        for parent in rebuilt {
+            self.emit(HierarchyEvent::Reordered { parent });
+        }
+        report
+    }
+}
+
+// 컴포넌트가 없는 엔티티는 뒤로 보낸다
+fn compare_components<'v, T, V>(components: V, a: EntityId, b: EntityId) -> std::cmp::Ordering
+    where
+        T: Ord + 'v,
+        V: Get<Out = &'v T> + Copy,
+{
+    match (components.get(a), components.get(b)) {
+        (Ok(a), Ok(b)) => a.cmp(b),
+        (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+        (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+        (Err(_), Err(_)) => std::cmp::Ordering::Equal,
+    }
+}
+
+// clone_subtree 의 원본 → 복사본 대응
+pub struct EntityMap(HashMap<EntityId, EntityId>);
+
+impl EntityMap {
+    // 하위 트리 밖을 가리키는 ID 는 그대로 둔다
+    pub fn get(&self, id: EntityId) -> EntityId {
+        self.0.get(&id).copied().unwrap_or(id)
+    }
+}
+
+// clone_subtree 로 복제할 수 있는 컴포넌트.
+// 하위 트리 안을 가리키는 EntityId 필드는 map 으로 바꿔야 한다.
+pub trait CloneComponent: Component + Send + Sync {
+    fn clone_mapped(&self, map: &EntityMap) -> Self;
+}
+
+type CloneFn = fn(&AllStorages, &EntityMap) -> Result<(), error::GetStorage>;
+
+// clone_subtree 가 복제할 컴포넌트 목록. 등록하지 않은 컴포넌트는 복사본에 없다.
+#[derive(Unique, Default)]
+pub struct CloneRegistry {
+    clones: Vec<CloneFn>,
+}
+
+impl CloneRegistry {
+    pub fn register<T: CloneComponent>(&mut self) {
+        self.clones.push(clone_component::<T>);
+    }
+}
+
+fn clone_component<T: CloneComponent>(all_storages: &AllStorages, map: &EntityMap) -> Result<(), error::GetStorage> {
+    let (entities, mut view) = all_storages.borrow::<(EntitiesViewMut, ViewMut<T>)>()?;
+    for (&source, &copy) in &map.0 {
+        if let Ok(component) = (&view).get(source) {
+            let component = component.clone_mapped(map);
+            entities.add_component(copy, &mut view, component);
+        }
+    }
+    Ok(())
+}
+
+// clone_subtree 실패 원인
+#[derive(Debug)]
+pub enum CloneError {
+    Hierarchy(HierarchyError),
+    Storage(error::GetStorage), // 저장소를 이미 다른 곳에서 빌린 경우
+}
+
+impl fmt::Display for CloneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloneError::Hierarchy(err) => write!(f, "invalid hierarchy: {}", err),
+            CloneError::Storage(err) => write!(f, "storage unavailable: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for CloneError {}
+
+impl From<HierarchyError> for CloneError {
+    fn from(err: HierarchyError) -> CloneError {
+        CloneError::Hierarchy(err)
+    }
+}
+
+impl From<error::GetStorage> for CloneError {
+    fn from(err: error::GetStorage) -> CloneError {
+        CloneError::Storage(err)
+    }
+}
+
+// root 와 자손들을 new_parent 아래에 복제하고 root 의 복사본을 반환한다.
+// R 관계의 링크와 CloneRegistry 에 등록된 컴포넌트를 복사한다.
+// 실패하면 만든 복사본을 모두 삭제한다.
+pub fn clone_subtree<R: Relation>(
+    all_storages: &mut AllStorages,
+    root: EntityId,
+    new_parent: EntityId,
+) -> Result<EntityId, CloneError> {
+    let (copy, map) = {
+        let (entities, parents, children) =
+            all_storages.borrow::<(EntitiesViewMut, ViewMut<Parent<R>>, ViewMut<Child<R>>)>()?;
+        let events = all_storages.borrow::<UniqueViewMut<HierarchyEvents<R>>>().ok();
+        HierarchyViews::new(entities, parents, children, events).copy_links(root, new_parent)?
+    };
+
+    let cloned = match all_storages.borrow::<UniqueView<CloneRegistry>>() {
+        Ok(registry) => registry.clones.iter().try_for_each(|clone| clone(all_storages, &map)),
+        Err(_) => Ok(()),
+    };
+    if let Err(err) = cloned {
+        if let Ok(mut hierarchy) = all_storages.borrow::<(EntitiesViewMut, ViewMut<Parent<R>>, ViewMut<Child<R>>)>() {
+            hierarchy.detach(copy);
+        }
+        for &id in map.0.values() {
+            all_storages.delete_entity(id);
+        }
+        return Err(err.into());
+    }
+    Ok(copy)
+}
+
+// HierarchyTransaction 에 기록되는 변경
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyOp {
+    Attach { id: EntityId, parent: EntityId },
+    Detach { id: EntityId },
+    Remove { id: EntityId },
+}
+
+// 실패한 변경의 순서와 원인
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionError {
+    pub index: usize,
+    pub error: HierarchyError,
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operation {} failed: {}", self.index, self.error)
+    }
+}
+
+impl std::error::Error for TransactionError {}
+
+// 여러 변경을 모아 두었다가 한꺼번에 적용한다.
+// 하나라도 실패하면 계층은 apply 이전 상태 그대로 남는다.
+#[derive(Debug, Default)]
+pub struct HierarchyTransaction {
+    ops: Vec<HierarchyOp>,
+}
+
+impl HierarchyTransaction {
+    pub fn new() -> HierarchyTransaction {
+        HierarchyTransaction::default()
+    }
+
+    pub fn attach(mut self, id: EntityId, parent: EntityId) -> HierarchyTransaction {
+        self.ops.push(HierarchyOp::Attach { id, parent });
+        self
+    }
+
+    pub fn detach(mut self, id: EntityId) -> HierarchyTransaction {
+        self.ops.push(HierarchyOp::Detach { id });
+        self
+    }
+
+    pub fn remove(mut self, id: EntityId) -> HierarchyTransaction {
+        self.ops.push(HierarchyOp::Remove { id });
+        self
+    }
+
+    pub fn ops(&self) -> &[HierarchyOp] {
+        &self.ops
+    }
+
+    // 모든 변경을 먼저 검증한 뒤 적용한다. 이벤트는 전부 성공했을 때만 기록된다.
+    pub fn apply<'v, H>(self, hierarchy: &mut H) -> Result<(), TransactionError>
+        where
+            H: HierarchyStorages<'v>,
+    {
+        let touched = self.validate(hierarchy)?;
+        let snapshot = LinkSnapshot::take(hierarchy, touched);
+
+        let mut buffered = BufferedEvents {
+            inner: &mut *hierarchy,
+            events: Vec::new(),
+        };
+        for (index, op) in self.ops.iter().enumerate() {
+            let result = match *op {
+                HierarchyOp::Attach { id, parent } => buffered.attach(id, parent),
+                HierarchyOp::Detach { id } => {
+                    buffered.detach(id);
+                    Ok(())
+                }
+                HierarchyOp::Remove { id } => {
+                    buffered.remove(id);
+                    Ok(())
+                }
+            };
+            if let Err(error) = result {
+                snapshot.restore(&mut *buffered.inner);
+                return Err(TransactionError { index, error });
+            }
+        }
+
+        let events = buffered.events;
+        for event in events {
+            hierarchy.emit(event);
+        }
+        Ok(())
+    }
+
+    // 저장소를 바꾸지 않고 부모 관계만 흉내 내어 순서대로 검증한다.
+    // 적용 중에 링크가 바뀔 수 있는 엔티티들을 반환한다.
+    fn validate<'v, H>(&self, hierarchy: &mut H) -> Result<HashSet<EntityId>, TransactionError>
+        where
+            H: HierarchyStorages<'v>,
+    {
+        let (entities, parents, children) = hierarchy.storages();
+        let (parents, children) = (&*parents, &*children);
+
+        // 변경된 부모. None 은 루트가 된 엔티티
+        let mut overlay: HashMap<EntityId, Option<EntityId>> = HashMap::new();
+        let parent_of = |overlay: &HashMap<EntityId, Option<EntityId>>, id: EntityId| {
+            overlay
+                .get(&id)
+                .copied()
+                .unwrap_or_else(|| children.get(id).ok().map(|child| child.parent))
+        };
+
+        let mut touched = HashSet::new();
+        for (index, op) in self.ops.iter().enumerate() {
+            let fail = |error| TransactionError { index, error };
+
+            let id = match *op {
+                HierarchyOp::Attach { id, .. } | HierarchyOp::Detach { id } | HierarchyOp::Remove { id } => id,
+            };
+            if !entities.is_alive(id) {
+                return Err(fail(HierarchyError::DeadEntity));
+            }
+            touched.insert(id);
+            if let Ok(child) = children.get(id) {
+                touched.insert(child.parent);
+            }
+
+            match *op {
+                HierarchyOp::Attach { id, parent } => {
+                    if !entities.is_alive(parent) {
+                        return Err(fail(HierarchyError::DeadEntity));
+                    }
+                    if id == parent {
+                        return Err(fail(HierarchyError::SelfParent));
+                    }
+                    let mut ancestor = Some(parent);
+                    while let Some(current) = ancestor {
+                        if current == id {
+                            return Err(fail(HierarchyError::WouldCreateCycle));
+                        }
+                        ancestor = parent_of(&overlay, current);
+                    }
+                    touched.insert(parent);
+                    overlay.insert(id, Some(parent));
+                }
+                HierarchyOp::Detach { id } => {
+                    overlay.insert(id, None);
+                }
+                HierarchyOp::Remove { id } => {
+                    overlay.insert(id, None);
+                    let orphans = (parents, children)
+                        .children(id)
+                        .chain(overlay.iter().filter(|(_, parent)| **parent == Some(id)).map(|(id, _)| *id))
+                        .filter(|&child| parent_of(&overlay, child) == Some(id))
+                        .collect::<Vec<_>>();
+                    for orphan in orphans {
+                        overlay.insert(orphan, None);
+                    }
+                }
+            }
+        }
+
+        // 바뀐 부모들의 기존 자식들은 형제 링크가 바뀔 수 있다
+        let siblings = touched
+            .iter()
+            .flat_map(|&id| (parents, children).children(id))
+            .collect::<Vec<_>>();
+        touched.extend(siblings);
+        Ok(touched)
+    }
+}
+
+// 적용 중 발생한 이벤트를 모아 두는 래퍼
+struct BufferedEvents<'h, H> {
+    inner: &'h mut H,
+    events: Vec<HierarchyEvent>,
+}
+
+impl<'h, 'v, H: HierarchyStorages<'v>> HierarchyStorages<'v> for BufferedEvents<'h, H> {
+    type Relation = H::Relation;
+
+    fn storages(
+        &mut self,
+    ) -> (
+        &mut EntitiesViewMut<'v>,
+        &mut ViewMut<'v, Parent<H::Relation>>,
+        &mut ViewMut<'v, Child<H::Relation>>,
+    ) {
+        self.inner.storages()
+    }
+    fn emit(&mut self, event: HierarchyEvent) {
+        self.events.push(event);
+    }
+}
+
+// (num_children, first_child)
+type ParentLink = (usize, EntityId);
+// (parent, prev, next)
+type ChildLink = (EntityId, EntityId, EntityId);
+
+// 되돌리기용 Parent/Child 값 복사본
+pub(crate) struct LinkSnapshot {
+    links: Vec<(EntityId, Option<ParentLink>, Option<ChildLink>)>,
+}
+
+impl LinkSnapshot {
+    pub(crate) fn take<'v, H: HierarchyStorages<'v>>(hierarchy: &mut H, ids: HashSet<EntityId>) -> LinkSnapshot {
+        let (_, parents, children) = hierarchy.storages();
+        LinkSnapshot {
+            links: ids
+                .into_iter()
+                .map(|id| {
+                    (
+                        id,
+                        (&*parents).get(id).ok().map(|parent| (parent.num_children, parent.first_child)),
+                        (&*children).get(id).ok().map(|child| (child.parent, child.prev, child.next)),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn restore<'v, H: HierarchyStorages<'v>>(&self, hierarchy: &mut H) {
+        let (entities, parents, children) = hierarchy.storages();
+        for &(id, parent, child) in &self.links {
+            match parent {
+                Some((num_children, first_child)) => entities.add_component(
+                    id,
+                    &mut *parents,
+                    Parent {
+                        num_children,
+                        first_child,
+                        relation: PhantomData,
+                    },
+                ),
+                None => {
+                    parents.remove(id);
+                }
+            }
+            match child {
+                Some((parent, prev, next)) => entities.add_component(
+                    id,
+                    &mut *children,
+                    Child {
+                        parent,
+                        prev,
+                        next,
+                        relation: PhantomData,
+                    },
+                ),
+                None => {
+                    children.remove(id);
+                }
+            }
+        }
+    }
+}
+
+// 링에서 id 를 빼서 prev 다음에 다시 연결한다. first_child 는 호출하는 쪽에서 관리한다.
+// parent 의 자식 링을 children 순서로 다시 잇는다
+fn relink_children<R: Relation>(
+    parents: &mut ViewMut<'_, Parent<R>>,
+    children_storage: &mut ViewMut<'_, Child<R>>,
+    parent: EntityId,
+    children: &[EntityId],
+) {
+    parents[parent].first_child = children[0];
+    for pair in children.windows(2) {
+        children_storage[pair[0]].next = pair[1];
+        children_storage[pair[1]].prev = pair[0];
+    }
+    let last = *children.last().unwrap();
+    children_storage[children[0]].prev = last;
+    children_storage[last].next = children[0];
+}
+
+fn relink_after<R: Relation>(children: &mut ViewMut<'_, Child<R>>, id: EntityId, prev: EntityId) {
+    if id == prev {
+        return;
+    }
+    let (old_prev, old_next) = (children[id].prev, children[id].next);
+    children[old_prev].next = old_next;
+    children[old_next].prev = old_prev;
+
+    let next = children[prev].next;
+    children[id].prev = prev;
+    children[id].next = next;
+    children[prev].next = id;
+    children[next].prev = id;
+}
+
+// 이미 parent 의 자식인 id 를 index 위치로 옮긴다
+fn place_child_at<R: Relation>(
+    parents: &mut ViewMut<'_, Parent<R>>,
+    children: &mut ViewMut<'_, Child<R>>,
+    parent: EntityId,
+    id: EntityId,
+    index: usize,
+) {
+    let first_child = parents[parent].first_child;
+    if index == 0 {
+        // 원형이므로 첫번째 자식 앞에 두고 first_child 만 바꾸면 된다
+        let prev = children[first_child].prev;
+        relink_after(children, id, prev);
+        parents[parent].first_child = id;
+    } else {
+        if first_child == id {
+            parents[parent].first_child = children[id].next;
+        }
+        let prev = (&*parents, &*children)
+            .children(parent)
+            .filter(|&child| child != id)
+            .nth(index - 1)
+            .unwrap();
+        relink_after(children, id, prev);
+    }
+}
+
+// 자식들 열거자
+pub struct ChildrenIter<C> {
+    get_child: C,// 자식 View로 설정
+    cursor: (EntityId, usize),// (첫번째 자식 Entity ID, 자식 갯수)로 설정
+}
+impl<'a, R, C> Iterator for ChildrenIter<C>
+    where
+        R: Relation,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    type Item = EntityId;// 항목은 EntityId
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cursor.1 > 0 { // 자식 갯수
+            self.cursor.1 -= 1;
+            let ret = self.cursor.0;// 해당 Entity ID 반환
+            self.cursor.0 = self.get_child.get(self.cursor.0).unwrap().next;//
+            Some(ret)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct AncestorIter<C> {
+    get_child: C,// 자식 View로 설정
+    cursor: EntityId,// 현재 Entity ID로 설정
+}
+
+impl<'a, R, C> Iterator for AncestorIter<C>
+    where
+        R: Relation,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.get_child.get(self.cursor).ok().map(|child| {
+            self.cursor = child.parent;
+            child.parent
+        })
+    }
+}
+
+pub struct DescendantsIter<P, C> {
+    get_parent: P,// 부모 View로 설정
+    get_child: C,// 자식 View로 설정
+    cursors: Vec<(EntityId, usize)>,// [(parent.first_child, parent.num_children)]로 설정
+}
+
+impl<'a, R, P, C> Iterator for DescendantsIter<P, C>
+    where
+        R: Relation,
+        P: Get<Out = &'a Parent<R>> + Copy,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(cursor) = self.cursors.last_mut() {
+            if cursor.1 > 0 {
+                cursor.1 -= 1;
+                let ret = cursor.0;
+                cursor.0 = self.get_child.get(cursor.0).unwrap().next;
+                if let Ok(parent) = self.get_parent.get(ret) {
+                    self.cursors.push((parent.first_child, parent.num_children));
+                }
+                Some(ret)
+            } else {
+                self.cursors.pop();
+                self.next()
+            }
+        } else {
+            None
+        }
+    }
+}
+
+// 너비 우선 자손 열거자
+pub struct DescendantsBfsIter<P, C> {
+    get_parent: P,
+    get_child: C,
+    queue: VecDeque<(EntityId, usize)>,// 방문할 형제 링 (first_child, num_children)
+}
+
+impl<'a, R, P, C> Iterator for DescendantsBfsIter<P, C>
+    where
+        R: Relation,
+        P: Get<Out = &'a Parent<R>> + Copy,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(cursor) = self.queue.front_mut() {
+            if cursor.1 > 0 {
+                cursor.1 -= 1;
+                let ret = cursor.0;
+                cursor.0 = self.get_child.get(cursor.0).unwrap().next;
+                if let Ok(parent) = self.get_parent.get(ret) {
+                    self.queue.push_back((parent.first_child, parent.num_children));
+                }
+                return Some(ret);
+            }
+            self.queue.pop_front();
+        }
+        None
+    }
+}
+
+// 후위 자손 열거자. 자식이 항상 부모보다 먼저 나오므로 아래에서부터 삭제할 때 사용한다.
+pub struct DescendantsPostOrderIter<P, C> {
+    get_parent: P,
+    get_child: C,
+    cursors: Vec<(EntityId, (EntityId, usize))>,// (링의 부모, (다음 자식, 남은 자식 갯수))
+}
+
+impl<'a, R, P, C> Iterator for DescendantsPostOrderIter<P, C>
+    where
+        R: Relation,
+        P: Get<Out = &'a Parent<R>> + Copy,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((owner, cursor)) = self.cursors.last_mut() {
+            if cursor.1 > 0 {
+                cursor.1 -= 1;
+                let id = cursor.0;
+                cursor.0 = self.get_child.get(cursor.0).unwrap().next;
+                match self.get_parent.get(id) {
+                    // 자식들을 먼저 방문
+                    Ok(parent) => self.cursors.push((id, (parent.first_child, parent.num_children))),
+                    Err(_) => return Some(id),
+                }
+            } else {
+                let owner = *owner;
+                self.cursors.pop();
+                // 시작 엔티티는 자손이 아니다
+                if !self.cursors.is_empty() {
+                    return Some(owner);
+                }
+            }
+        }
+        None
+    }
+}
+
+// 깊이를 함께 반환하는 전위 자손 열거자. 자식의 깊이는 1이다.
+pub struct DescendantsWithDepthIter<P, C> {
+    get_parent: P,
+    get_child: C,
+    cursors: Vec<(EntityId, usize)>,
+    max_depth: usize,
+}
+
+impl<'a, R, P, C> Iterator for DescendantsWithDepthIter<P, C>
+    where
+        R: Relation,
+        P: Get<Out = &'a Parent<R>> + Copy,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    type Item = (EntityId, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(cursor) = self.cursors.last_mut() {
+            if cursor.1 > 0 {
+                cursor.1 -= 1;
+                let ret = cursor.0;
+                cursor.0 = self.get_child.get(cursor.0).unwrap().next;
+                let depth = self.cursors.len();
+                if depth < self.max_depth {
+                    if let Ok(parent) = self.get_parent.get(ret) {
+                        self.cursors.push((parent.first_child, parent.num_children));
+                    }
+                }
+                return Some((ret, depth));
+            }
+            self.cursors.pop();
+        }
+        None
+    }
+}
+
+pub trait HierarchyIter<'a, P, C> {
+    fn ancestors(&self, id: EntityId) -> AncestorIter<C>;//조상들
+    fn children(&self, id: EntityId) -> ChildrenIter<C>;//자식들
+    fn descendants(&self, id: EntityId) -> DescendantsIter<P, C>;//자손들
+    fn descendants_bfs(&self, id: EntityId) -> DescendantsBfsIter<P, C>;//자손들 (너비 우선)
+    fn descendants_post_order(&self, id: EntityId) -> DescendantsPostOrderIter<P, C>;//자손들 (후위)
+    // max_depth 보다 깊은 자손은 건너뛴다
+    fn descendants_with_depth(&self, id: EntityId, max_depth: usize) -> DescendantsWithDepthIter<P, C>;
+    fn siblings(&self, id: EntityId) -> ChildrenIter<C>;//자신을 제외한 형제들
+    fn root_of(&self, id: EntityId) -> EntityId;//최상위 조상 (없으면 자신)
+    fn depth(&self, id: EntityId) -> usize;//루트의 깊이는 0
+    fn is_ancestor_of(&self, ancestor: EntityId, id: EntityId) -> bool;
+    // 자기 자신도 공통 조상이 될 수 있다. 다른 트리이면 None.
+    fn lowest_common_ancestor(&self, a: EntityId, b: EntityId) -> Option<EntityId>;
+    // a 에서 공통 조상을 거쳐 b 까지 (양 끝 포함). 다른 트리이면 None.
+    fn path_between(&self, a: EntityId, b: EntityId) -> Option<Vec<EntityId>>;
+}
+
+// P : 부모
+// C : 자식
+// (P, C) : P, C 형태의 튜플에 대한 구현
+// R : 관계. P, C 가 같은 관계의 저장소여야 한다.
+impl<'a, R, P, C> HierarchyIter<'a, P, C> for (P, C)
+    where
+        R: Relation,
+        P: Get<Out = &'a Parent<R>> + Copy,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    fn ancestors(&self, id: EntityId) -> AncestorIter<C> {
+        let (_, children) = self;
+
+        AncestorIter {
+            get_child: *children,
+            cursor: id,
+        }
+    }
+
+    fn children(&self, id: EntityId) -> ChildrenIter<C> {
+        let (parents, children) = self;
+
+        ChildrenIter {
+            get_child: *children,
+            cursor: parents
+                .get(id)
+                .map_or((id, 0), |parent| (parent.first_child, parent.num_children)),
+        }
+    }
+
+    fn descendants(&self, id: EntityId) -> DescendantsIter<P, C> {
+        let (parents, children) = self;
+
+        DescendantsIter {
+            get_parent: *parents,
+            get_child: *children,
+            cursors: parents.get(id).map_or_else(
+                |_| Vec::new(),
+                |parent| vec![(parent.first_child, parent.num_children)],
+            ),
+        }
+    }
+
+    fn descendants_bfs(&self, id: EntityId) -> DescendantsBfsIter<P, C> {
+        let (parents, children) = self;
+
+        DescendantsBfsIter {
+            get_parent: *parents,
+            get_child: *children,
+            queue: parents
+                .get(id)
+                .map_or_else(|_| VecDeque::new(), |parent| {
+                    VecDeque::from(vec![(parent.first_child, parent.num_children)])
+                }),
+        }
+    }
+
+    fn descendants_post_order(&self, id: EntityId) -> DescendantsPostOrderIter<P, C> {
+        let (parents, children) = self;
+
+        DescendantsPostOrderIter {
+            get_parent: *parents,
+            get_child: *children,
+            cursors: parents.get(id).map_or_else(
+                |_| Vec::new(),
+                |parent| vec![(id, (parent.first_child, parent.num_children))],
+            ),
+        }
+    }
+
+    fn descendants_with_depth(&self, id: EntityId, max_depth: usize) -> DescendantsWithDepthIter<P, C> {
+        let (parents, children) = self;
+
+        DescendantsWithDepthIter {
+            get_parent: *parents,
+            get_child: *children,
+            cursors: match parents.get(id) {
+                Ok(parent) if max_depth > 0 => vec![(parent.first_child, parent.num_children)],
+                _ => Vec::new(),
+            },
+            max_depth,
+        }
+    }
+
+    fn siblings(&self, id: EntityId) -> ChildrenIter<C> {
+        let (parents, children) = self;
+
+        ChildrenIter {
+            get_child: *children,
+            cursor: children.get(id).map_or((id, 0), |child| {
+                (child.next, parents.get(child.parent).unwrap().num_children - 1)
+            }),
+        }
+    }
+
+    fn root_of(&self, id: EntityId) -> EntityId {
+        self.ancestors(id).last().unwrap_or(id)
+    }
+
+    fn depth(&self, id: EntityId) -> usize {
+        self.ancestors(id).count()
+    }
+
+    fn is_ancestor_of(&self, ancestor: EntityId, id: EntityId) -> bool {
+        self.ancestors(id).any(|id| id == ancestor)
+    }
+
+    fn lowest_common_ancestor(&self, a: EntityId, b: EntityId) -> Option<EntityId> {
+        let a_line = std::iter::once(a)
+            .chain(self.ancestors(a))
+            .collect::<HashSet<_>>();
+
+        std::iter::once(b)
+            .chain(self.ancestors(b))
+            .find(|id| a_line.contains(id))
+    }
+
+    fn path_between(&self, a: EntityId, b: EntityId) -> Option<Vec<EntityId>> {
+        let lca = self.lowest_common_ancestor(a, b)?;
+
+        let mut path = std::iter::once(a)
+            .chain(self.ancestors(a))
+            .take_while(|&id| id != lca)
+            .collect::<Vec<_>>();
+        path.push(lca);
+
+        let down = std::iter::once(b)
+            .chain(self.ancestors(b))
+            .take_while(|&id| id != lca)
+            .collect::<Vec<_>>();
+        path.extend(down.into_iter().rev());
+        Some(path)
+    }
+}
+
+// 루트(Parent 는 있고 Child 는 없는 엔티티)마다 f(root, 자손들) 을 rayon 작업 스레드에서 호출한다.
+// 트리끼리는 엔티티를 공유하지 않으므로 서로 기다리지 않고 처리할 수 있다.
+pub fn par_for_each_subtree<'a, 'v, R, F>(parents: &'a View<'v, Parent<R>>, children: &'a View<'v, Child<R>>, f: F)
+    where
+        R: Relation,
+        View<'v, Parent<R>>: Sync,
+        View<'v, Child<R>>: Sync,
+        F: Fn(EntityId, DescendantsIter<&'a View<'v, Parent<R>>, &'a View<'v, Child<R>>>) + Send + Sync,
+{
+    let roots = parents
+        .iter()
+        .with_id()
+        .map(|(id, _)| id)
+        .filter(|id| !children.contains(*id))
+        .collect::<Vec<_>>();
+
+    roots
+        .into_par_iter()
+        .for_each(|root| f(root, (parents, children).descendants(root)));
+}
+
+// 계층 불변식 위반 항목
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyViolation {
+    // num_children 과 실제 링 길이가 다름
+    ChildCountMismatch { parent: EntityId, num_children: usize, ring_len: usize },
+    // first_child 에 Child 컴포넌트가 없거나 다른 부모의 자식임
+    FirstChildNotInRing { parent: EntityId, first_child: EntityId },
+    // next 의 prev 가 자신을 가리키지 않음
+    BrokenLink { id: EntityId, next: EntityId },
+    // 링 안의 자식이 다른 부모를 가리킴
+    WrongParent { child: EntityId, expected: EntityId, found: EntityId },
+    // Child.parent 에 Parent 컴포넌트가 없음
+    MissingParent { child: EntityId, parent: EntityId },
+    // 부모의 링에 포함되지 않은 자식
+    NotInRing { child: EntityId, parent: EntityId },
+    // Child.parent 를 따라가면 다시 돌아오는 순환. entity 는 순환에 속한 가장 작은 ID.
+    Cycle { entity: EntityId },
+}
+
+#[derive(Debug, Default)]
+pub struct HierarchyReport {
+    violations: Vec<HierarchyViolation>,
+}
+
+impl HierarchyReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    pub fn violations(&self) -> &[HierarchyViolation] {
+        &self.violations
+    }
+}
+
+// Checks every invariant the Parent/Child rings rely on.
+pub fn validate_hierarchy<R: Relation>(parents: &View<Parent<R>>, children: &View<Child<R>>) -> HierarchyReport {
+    let parent_ids = parents.iter().with_id().map(|(id, _)| id).collect::<Vec<_>>();
+    let child_ids = children.iter().with_id().map(|(id, _)| id).collect::<Vec<_>>();
+
+    collect_violations(&parent_ids, &child_ids, parents, children)
+}
+
+fn collect_violations<'a, R, P, C>(
+    parent_ids: &[EntityId],
+    child_ids: &[EntityId],
+    parents: P,
+    children: C,
+) -> HierarchyReport
+    where
+        R: Relation,
+        P: Get<Out = &'a Parent<R>> + Copy,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    let mut violations = Vec::new();
+    let mut in_ring: HashSet<EntityId> = HashSet::new();
+
+    for &parent_id in parent_ids {
+        let parent = parents.get(parent_id).unwrap();
+        let first_child = parent.first_child;
+
+        match children.get(first_child) {
+            Ok(child) if child.parent == parent_id => {}
+            _ => {
+                violations.push(HierarchyViolation::FirstChildNotInRing { parent: parent_id, first_child });
+                continue;
+            }
+        }
+
+        // 깨진 링에서 무한 루프를 막기 위해 자식 수만큼만 따라간다
+        let mut ring_len = 0;
+        let mut cursor = first_child;
+        while ring_len < child_ids.len() {
+            let child = children.get(cursor).unwrap();
+            ring_len += 1;
+
+            if child.parent == parent_id {
+                in_ring.insert(cursor);
+            } else {
+                violations.push(HierarchyViolation::WrongParent {
+                    child: cursor,
+                    expected: parent_id,
+                    found: child.parent,
+                });
+            }
+
+            match children.get(child.next) {
+                Ok(next) if next.prev == cursor => {}
+                _ => {
+                    violations.push(HierarchyViolation::BrokenLink { id: cursor, next: child.next });
+                    break;
+                }
+            }
+
+            cursor = child.next;
+            if cursor == first_child {
+                break;
+            }
+        }
+
+        if ring_len != parent.num_children {
+            violations.push(HierarchyViolation::ChildCountMismatch {
+                parent: parent_id,
+                num_children: parent.num_children,
+                ring_len,
+            });
+        }
+    }
+
+    for &child_id in child_ids {
+        let parent = children.get(child_id).unwrap().parent;
+        if parents.get(parent).is_err() {
+            violations.push(HierarchyViolation::MissingParent { child: child_id, parent });
+        } else if !in_ring.contains(&child_id) {
+            violations.push(HierarchyViolation::NotInRing { child: child_id, parent });
+        }
+    }
+
+    // Child.parent 를 따라 올라가며 순환을 찾는다. 이미 확인한 엔티티에서는 멈춘다.
+    let mut checked: HashSet<EntityId> = HashSet::new();
+    for &child_id in child_ids {
+        let mut path = Vec::new();
+        let mut cursor = child_id;
+        while !checked.contains(&cursor) {
+            if let Some(start) = path.iter().position(|&id| id == cursor) {
+                let entity = path[start..].iter().copied().min().unwrap();
+                violations.push(HierarchyViolation::Cycle { entity });
+                break;
+            }
+            path.push(cursor);
+            match children.get(cursor) {
+                Ok(child) => cursor = child.parent,
+                Err(_) => break,
+            }
+        }
+        checked.extend(path);
+    }
+
+    HierarchyReport { violations }
+}
+
+// 부모가 삭제되었을 때 남은 자식들의 처리 방법
+#[derive(Unique, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OrphanPolicy {
+    #[default]
+    Detach,   // 루트가 된다
+    Reparent, // 살아있는 가장 가까운 조상에 붙는다
+    Cascade,  // 하위 트리 전체를 삭제한다
+}
+
+// World::delete_entity 가 남긴 R 관계의 링크를 정리한다.
+// 삭제된 Parent/Child 는 Deletion 추적으로 알아낸다.
+pub fn hierarchy_cleanup_system<R: Relation>(mut all_storages: AllStoragesViewMut) {
+    let policy = all_storages
+        .borrow::<UniqueView<OrphanPolicy>>()
+        .map(|policy| *policy)
+        .unwrap_or_default();
+
+    loop {
+        // HierarchyEvents 가 있으면 이벤트도 기록한다
+        let to_delete = if let Ok(mut hierarchy) = all_storages.borrow::<(
+            EntitiesViewMut,
+            ViewMut<Parent<R>>,
+            ViewMut<Child<R>>,
+            UniqueViewMut<HierarchyEvents<R>>,
+        )>() {
+            cleanup_deleted(&mut hierarchy, policy)
+        } else {
+            let mut hierarchy = all_storages
+                .borrow::<(EntitiesViewMut, ViewMut<Parent<R>>, ViewMut<Child<R>>)>()
+                .unwrap();
+            cleanup_deleted(&mut hierarchy, policy)
+        };
+        if to_delete.is_empty() {
+            break;
+        }
+        // Cascade 로 삭제된 엔티티는 다음 루프에서 정리된다
+        for id in to_delete {
+            all_storages.delete_entity(id);
+        }
+    }
+}
+
+// 관계마다 하나씩 필요하다.
+// Cascade 로 삭제된 엔티티가 다른 관계에도 속해 있으면 그 관계의 정리 시스템이 다음 실행 때 처리한다.
+pub fn hierarchy_cleanup_workload<R: Relation>() -> Workload {
+    hierarchy_cleanup_system::<R>.into_workload()
+}
+
+// 삭제된 자식은 저장소에 없으므로 삭제 당시 값을 대신 수정한다
+fn child_link<'m, R: Relation>(
+    children: &'m mut ViewMut<'_, Child<R>>,
+    dead_children: &'m mut HashMap<EntityId, Child<R>>,
+    id: EntityId,
+) -> Option<&'m mut Child<R>> {
+    if let Some(child) = dead_children.get_mut(&id) {
+        Some(child)
+    } else if children.contains(id) {
+        Some(&mut children[id])
+    } else {
+        None
+    }
+}
+
+// Cascade 정책으로 추가 삭제해야 할 엔티티를 반환한다.
+fn cleanup_deleted<'v, H>(hierarchy: &mut H, policy: OrphanPolicy) -> Vec<EntityId>
+    where
+        H: HierarchyStorages<'v>,
+{
+    let (_, parents, children) = hierarchy.storages();
+    // 삭제 당시 값을 복사해 두고 추적 기록을 비운다. 다음 실행 때 다시 처리하지 않는다.
+    let deleted_children = children
+        .deleted()
+        .map(|(id, child)| (id, Child { parent: child.parent, prev: child.prev, next: child.next, relation: PhantomData }))
+        .collect::<Vec<_>>();
+    let deleted_parents = parents
+        .deleted()
+        .map(|(id, parent)| {
+            (id, Parent { num_children: parent.num_children, first_child: parent.first_child, relation: PhantomData })
+        })
+        .collect::<Vec<_>>();
+    children.clear_all_deleted();
+    parents.clear_all_deleted();
+    let dead_child_ids = deleted_children.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let dead_parent_ids = deleted_parents.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let mut dead_children: HashMap<EntityId, Child<H::Relation>> = deleted_children.into_iter().collect();
+    let mut dead_parents: HashMap<EntityId, Parent<H::Relation>> = deleted_parents.into_iter().collect();
+
+    // 1. 삭제된 자식을 형제 링에서 제외
+    for &id in &dead_child_ids {
+        let (parent_id, prev, next) = {
+            let child = &dead_children[&id];
+            (child.parent, child.prev, child.next)
+        };
+
+        let parent = if let Some(parent) = dead_parents.get_mut(&parent_id) {
+            parent
+        } else if parents.contains(parent_id) {
+            &mut parents[parent_id]
+        } else {
+            continue;
+        };
+
+        parent.num_children -= 1;
+        if parent.num_children == 0 {
+            if !dead_parents.contains_key(&parent_id) {
+                parents.remove(parent_id);
+            }
+            continue;
+        }
+        if parent.first_child == id {
+            parent.first_child = next;
+        }
+
+        if let Some(child) = child_link(children, &mut dead_children, prev) {
+            child.next = next;
+        }
+        if let Some(child) = child_link(children, &mut dead_children, next) {
+            child.prev = prev;
+        }
+    }
+
+    let mut removed = dead_child_ids;
+    removed.extend(dead_parent_ids.iter().copied().filter(|id| !dead_children.contains_key(id)));
+    for id in removed {
+        hierarchy.emit(HierarchyEvent::Removed { id });
+    }
+
+    // 2. 삭제된 부모의 남은 자식들을 정책에 따라 처리
+    let mut to_delete = Vec::new();
+    for parent_id in dead_parent_ids {
+        let (entities, _, children) = hierarchy.storages();
+        let parent = &dead_parents[&parent_id];
+
+        let mut orphans = Vec::with_capacity(parent.num_children);
+        let mut cursor = parent.first_child;
+        for _ in 0..parent.num_children {
+            orphans.push(cursor);
+            cursor = children[cursor].next;
+        }
+
+        // 삭제된 부모의 살아있는 가장 가까운 조상
+        let mut ancestor = dead_children.get(&parent_id).map(|child| child.parent);
+        while let Some(id) = ancestor {
+            if entities.is_alive(id) {
+                break;
+            }
+            ancestor = dead_children.get(&id).map(|child| child.parent);
+        }
+
+        for orphan in orphans {
+            let (_, parents, children) = hierarchy.storages();
+            children.remove(orphan);
+            if policy == OrphanPolicy::Cascade {
+                to_delete.push(orphan);
+                to_delete.extend((&*parents, &*children).descendants(orphan));
+            }
+            hierarchy.emit(HierarchyEvent::Detached { child: orphan, old_parent: parent_id });
+
+            if let (OrphanPolicy::Reparent, Some(ancestor)) = (policy, ancestor) {
+                // 고아는 살아있고 순환도 불가능하므로 실패하지 않는다
+                let _ = hierarchy.attach(orphan, ancestor);
+            }
+        }
+    }
+
+    to_delete
+}
+
+
+// 필요한 저장소를 내부에서 빌리는 편의 메서드.
+// 이름 없는 메서드는 기본 계층(DefaultRelation)을 사용한다.
+// 이미 같은 저장소를 빌린 상태에서 호출하면 panic 한다.
+pub trait HierarchyExt {
+    // Borrows everything needed to modify the `R` hierarchy.
+    fn hierarchy<R: Relation>(&self) -> HierarchyViews<'_, R>;
+    // Borrows the `R` hierarchy for reading only.
+    fn hierarchy_query<R: Relation>(&self) -> (View<'_, Parent<R>>, View<'_, Child<R>>);
+    // Copies `root`, its descendants and every component in `CloneRegistry` under `new_parent`.
+    fn clone_subtree(&mut self, root: EntityId, new_parent: EntityId) -> Result<EntityId, CloneError>;
+
+    // Sorts by the `T` component, borrowing its storage. Children without it go last.
+    fn sort_children_by_component<T: Component + Ord + Send + Sync>(&self, id: EntityId);
+    fn sort_descendants_by_component<T: Component + Ord + Send + Sync>(&self, id: EntityId);
+
+    fn attach(&self, id: EntityId, parent: EntityId) -> Result<(), HierarchyError> {
+        self.hierarchy::<DefaultRelation>().attach(id, parent)
+    }
+    fn attach_new(&self, parent: EntityId) -> Result<EntityId, HierarchyError> {
+        self.hierarchy::<DefaultRelation>().attach_new(parent)
+    }
+    // World/AllStorages 의 remove 와 겹치지 않도록 이름에 hierarchy 를 붙인다
+    fn detach_from_hierarchy(&self, id: EntityId) {
+        self.hierarchy::<DefaultRelation>().detach(id);
+    }
+    fn remove_from_hierarchy(&self, id: EntityId) {
+        self.hierarchy::<DefaultRelation>().remove(id);
+    }
+    fn remove_all_from_hierarchy(&self, id: EntityId) {
+        self.hierarchy::<DefaultRelation>().remove_all(id);
+    }
+    // Views can't outlive the call, so queries are collected.
+    fn parent_of(&self, id: EntityId) -> Option<EntityId> {
+        let (parents, children) = self.hierarchy_query::<DefaultRelation>();
+        (&parents, &children).ancestors(id).next()
+    }
+    fn children(&self, id: EntityId) -> Vec<EntityId> {
+        let (parents, children) = self.hierarchy_query::<DefaultRelation>();
+        (&parents, &children).children(id).collect()
+    }
+    fn ancestors(&self, id: EntityId) -> Vec<EntityId> {
+        let (parents, children) = self.hierarchy_query::<DefaultRelation>();
+        (&parents, &children).ancestors(id).collect()
+    }
+    fn descendants(&self, id: EntityId) -> Vec<EntityId> {
+        let (parents, children) = self.hierarchy_query::<DefaultRelation>();
+        (&parents, &children).descendants(id).collect()
+    }
+}
+
+impl HierarchyExt for World {
+    fn hierarchy<R: Relation>(&self) -> HierarchyViews<'_, R> {
+        let (entities, parents, children) = self
+            .borrow::<(EntitiesViewMut, ViewMut<Parent<R>>, ViewMut<Child<R>>)>()
+            .unwrap();
+        let events = self.borrow::<UniqueViewMut<HierarchyEvents<R>>>().ok();
+        HierarchyViews::new(entities, parents, children, events)
+    }
+
+    fn hierarchy_query<R: Relation>(&self) -> (View<'_, Parent<R>>, View<'_, Child<R>>) {
+        self.borrow::<(View<Parent<R>>, View<Child<R>>)>().unwrap()
+    }
+
+    fn sort_children_by_component<T: Component + Ord + Send + Sync>(&self, id: EntityId) {
+        let components = self.borrow::<View<T>>().unwrap();
+        self.hierarchy::<DefaultRelation>().sort_children_by_component(id, &components);
+    }
+
+    fn sort_descendants_by_component<T: Component + Ord + Send + Sync>(&self, id: EntityId) {
+        let components = self.borrow::<View<T>>().unwrap();
+        self.hierarchy::<DefaultRelation>().sort_descendants_by_component(id, &components);
+    }
+
+    fn clone_subtree(&mut self, root: EntityId, new_parent: EntityId) -> Result<EntityId, CloneError> {
+        self.run(|mut all_storages: AllStoragesViewMut| {
+            clone_subtree::<DefaultRelation>(&mut all_storages, root, new_parent)
+        })
+    }
+}
+
+// AllStoragesViewMut 에서도 역참조로 사용할 수 있다
+impl HierarchyExt for AllStorages {
+    fn hierarchy<R: Relation>(&self) -> HierarchyViews<'_, R> {
+        let (entities, parents, children) = self
+            .borrow::<(EntitiesViewMut, ViewMut<Parent<R>>, ViewMut<Child<R>>)>()
+            .unwrap();
+        let events = self.borrow::<UniqueViewMut<HierarchyEvents<R>>>().ok();
+        HierarchyViews::new(entities, parents, children, events)
+    }
+
+    fn hierarchy_query<R: Relation>(&self) -> (View<'_, Parent<R>>, View<'_, Child<R>>) {
+        self.borrow::<(View<Parent<R>>, View<Child<R>>)>().unwrap()
+    }
+
+    fn sort_children_by_component<T: Component + Ord + Send + Sync>(&self, id: EntityId) {
+        let components = self.borrow::<View<T>>().unwrap();
+        self.hierarchy::<DefaultRelation>().sort_children_by_component(id, &components);
+    }
+
+    fn sort_descendants_by_component<T: Component + Ord + Send + Sync>(&self, id: EntityId) {
+        let components = self.borrow::<View<T>>().unwrap();
+        self.hierarchy::<DefaultRelation>().sort_descendants_by_component(id, &components);
+    }
+
+    fn clone_subtree(&mut self, root: EntityId, new_parent: EntityId) -> Result<EntityId, CloneError> {
+        clone_subtree::<DefaultRelation>(self, root, new_parent)
+    }
+}
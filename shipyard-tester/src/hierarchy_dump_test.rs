@@ -0,0 +1,127 @@
+use crate::hierarchy::{Child, HierarchyIter, Parent, Relation};
+use shipyard::*;
+use std::fmt::Write;
+
+// 엔티티 하나를 출력할 문자열. 없으면 EntityId 의 Debug 출력을 사용한다.
+pub(crate) type LabelFn<'l> = &'l dyn Fn(EntityId) -> String;
+
+// root 가 None 이면 모든 트리를 루트 ID 순서로 출력한다
+fn dump_roots<R: Relation>(parents: &View<Parent<R>>, children: &View<Child<R>>, root: Option<EntityId>) -> Vec<EntityId> {
+    match root {
+        Some(root) => vec![root],
+        None => {
+            let mut roots = parents
+                .iter()
+                .with_id()
+                .map(|(id, _)| id)
+                .filter(|id| !children.contains(*id))
+                .collect::<Vec<_>>();
+            roots.sort();
+            roots
+        }
+    }
+}
+
+fn label_of(label: Option<LabelFn<'_>>, id: EntityId) -> String {
+    label.map_or_else(|| format!("{:?}", id), |label| label(id))
+}
+
+// 깊이마다 두 칸씩 들여쓴 텍스트
+pub(crate) fn dump_text<R: Relation>(
+    parents: &View<Parent<R>>,
+    children: &View<Child<R>>,
+    root: Option<EntityId>,
+    label: Option<LabelFn<'_>>,
+) -> String {
+    let mut out = String::new();
+    for root in dump_roots(parents, children, root) {
+        writeln!(out, "{}", label_of(label, root)).unwrap();
+        for (id, depth) in (parents, children).descendants_with_depth(root, usize::MAX) {
+            writeln!(out, "{}{}", "  ".repeat(depth), label_of(label, id)).unwrap();
+        }
+    }
+    out
+}
+
+// Graphviz DOT. `dot -Tsvg` 등으로 그림을 만들 수 있다.
+pub(crate) fn dump_dot<R: Relation>(
+    parents: &View<Parent<R>>,
+    children: &View<Child<R>>,
+    root: Option<EntityId>,
+    label: Option<LabelFn<'_>>,
+) -> String {
+    let node = |id: EntityId| format!("\"{:?}\"", id);
+    let escape = |text: String| text.replace('\\', "\\\\").replace('"', "\\\"");
+
+    let mut out = String::from("digraph hierarchy {\n");
+    for root in dump_roots(parents, children, root) {
+        writeln!(out, "    {} [label=\"{}\"];", node(root), escape(label_of(label, root))).unwrap();
+        for id in (parents, children).descendants(root) {
+            writeln!(out, "    {} [label=\"{}\"];", node(id), escape(label_of(label, id))).unwrap();
+        }
+        // 간선은 형제 순서대로 나열되므로 그림에서도 순서가 유지된다
+        for parent in std::iter::once(root).chain((parents, children).descendants(root)) {
+            for child in (parents, children).children(parent) {
+                writeln!(out, "    {} -> {};", node(parent), node(child)).unwrap();
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::hierarchy_dump_test::*;
+    use crate::hierarchy::Hierarchy;
+
+    #[test]
+    fn dump_test() {
+        let world = World::new();
+
+        let (root, e1, e2) = world.run(
+            |entities: EntitiesViewMut,
+             parents: ViewMut<Parent>,
+             children: ViewMut<Child>,
+             mut vm_pos: ViewMut<Pos>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root = hierarchy.0.add_entity(&mut vm_pos, Pos::new(0, 0));
+                let e1 = hierarchy.attach_new(root).unwrap();
+                let e2 = hierarchy.attach_new(e1).unwrap();
+                let _e3 = hierarchy.attach_new(root).unwrap();
+                hierarchy.0.add_component(e1, &mut vm_pos, Pos::new(1, 2));
+                let other = hierarchy.0.add_entity((), ());
+                let leaf = hierarchy.0.add_entity((), ());
+                hierarchy.attach(leaf, other).unwrap();
+                (root, e1, e2)
+            },
+        );
+
+        world.run(|parents: View<Parent>, children: View<Child>, v_pos: View<Pos>| {
+            let label = |id: EntityId| match v_pos.get(id) {
+                Ok(pos) => format!("pos ({}, {})", pos.0, pos.1),
+                Err(_) => String::from("\"none\""),
+            };
+
+            assert_eq!(
+                dump_text(&parents, &children, Some(root), Some(&label)),
+                "pos (0, 0)\n  pos (1, 2)\n    \"none\"\n  \"none\"\n"
+            );
+
+            let dot = dump_dot(&parents, &children, Some(e1), Some(&label));
+            assert_eq!(
+                dot,
+                format!(
+                    "digraph hierarchy {{\n    \"{:?}\" [label=\"pos (1, 2)\"];\n    \"{:?}\" [label=\"\\\"none\\\"\"];\n    \"{:?}\" -> \"{:?}\";\n}}\n",
+                    e1, e2, e1, e2
+                )
+            );
+
+            // 전체 숲: 두 트리, 엔티티 6개
+            assert_eq!(dump_text(&parents, &children, None, None).lines().count(), 6);
+            assert_eq!(dump_dot(&parents, &children, None, None).matches(" -> ").count(), 4);
+        });
+    }
+}
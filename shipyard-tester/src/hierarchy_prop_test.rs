@@ -0,0 +1,178 @@
+// 무작위 변경 순서를 참조 모델과 비교하는 속성 기반 테스트
+#[cfg(test)]
+mod tests {
+    use crate::hierarchy::*;
+    use proptest::prelude::*;
+    use shipyard::*;
+    use std::collections::HashMap;
+
+    // 인덱스는 현재 엔티티 목록 길이로 나눈 나머지를 사용한다
+    #[derive(Debug, Clone)]
+    enum Op {
+        Attach(usize, usize),
+        AttachNew(usize),
+        Detach(usize),
+        Remove(usize),
+        RemoveAll(usize),
+        Sort(usize, bool), // true 이면 ID 내림차순
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (any::<usize>(), any::<usize>()).prop_map(|(id, parent)| Op::Attach(id, parent)),
+            2 => any::<usize>().prop_map(Op::AttachNew),
+            2 => any::<usize>().prop_map(Op::Detach),
+            1 => any::<usize>().prop_map(Op::Remove),
+            1 => any::<usize>().prop_map(Op::RemoveAll),
+            1 => (any::<usize>(), any::<bool>()).prop_map(|(id, reverse)| Op::Sort(id, reverse)),
+        ]
+    }
+
+    // 부모 → 순서 있는 자식 목록
+    #[derive(Default)]
+    struct Model {
+        children: HashMap<EntityId, Vec<EntityId>>,
+    }
+
+    impl Model {
+        fn children(&self, id: EntityId) -> Vec<EntityId> {
+            self.children.get(&id).cloned().unwrap_or_default()
+        }
+
+        fn parent(&self, id: EntityId) -> Option<EntityId> {
+            self.children
+                .iter()
+                .find(|(_, children)| children.contains(&id))
+                .map(|(parent, _)| *parent)
+        }
+
+        fn ancestors(&self, mut id: EntityId) -> Vec<EntityId> {
+            let mut ancestors = Vec::new();
+            while let Some(parent) = self.parent(id) {
+                ancestors.push(parent);
+                id = parent;
+            }
+            ancestors
+        }
+
+        fn descendants(&self, id: EntityId) -> Vec<EntityId> {
+            let mut descendants = Vec::new();
+            for child in self.children(id) {
+                descendants.push(child);
+                descendants.extend(self.descendants(child));
+            }
+            descendants
+        }
+
+        fn detach(&mut self, id: EntityId) {
+            if let Some(parent) = self.parent(id) {
+                let siblings = self.children.get_mut(&parent).unwrap();
+                siblings.retain(|&sibling| sibling != id);
+                if siblings.is_empty() {
+                    self.children.remove(&parent);
+                }
+            }
+        }
+
+        fn attach(&mut self, id: EntityId, parent: EntityId) -> Result<(), HierarchyError> {
+            if id == parent {
+                return Err(HierarchyError::SelfParent);
+            }
+            if self.ancestors(parent).contains(&id) {
+                return Err(HierarchyError::WouldCreateCycle);
+            }
+            self.detach(id);
+            self.children.entry(parent).or_default().push(id);
+            Ok(())
+        }
+
+        fn remove(&mut self, id: EntityId) {
+            self.detach(id);
+            self.children.remove(&id);
+        }
+
+        fn remove_all(&mut self, id: EntityId) {
+            for child in self.children(id) {
+                self.remove_all(child);
+            }
+            self.remove(id);
+        }
+    }
+
+    fn run(ops: &[Op]) {
+        let world = World::new();
+        let mut hierarchy = world
+            .borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>)>()
+            .unwrap();
+        let mut model = Model::default();
+        let mut ids = (0..8).map(|_| hierarchy.0.add_entity((), ())).collect::<Vec<_>>();
+
+        for op in ops {
+            let pick = |index: usize| ids[index % ids.len()];
+            match *op {
+                Op::Attach(id, parent) => {
+                    let (id, parent) = (pick(id), pick(parent));
+                    assert_eq!(hierarchy.attach(id, parent), model.attach(id, parent), "{:?}", op);
+                }
+                Op::AttachNew(parent) => {
+                    let parent = pick(parent);
+                    let id = hierarchy.attach_new(parent).unwrap();
+                    model.attach(id, parent).unwrap();
+                    ids.push(id);
+                }
+                Op::Detach(id) => {
+                    hierarchy.detach(pick(id));
+                    model.detach(pick(id));
+                }
+                Op::Remove(id) => {
+                    hierarchy.remove(pick(id));
+                    model.remove(pick(id));
+                }
+                Op::RemoveAll(id) => {
+                    hierarchy.remove_all(pick(id));
+                    model.remove_all(pick(id));
+                }
+                Op::Sort(id, reverse) => {
+                    let id = pick(id);
+                    let compare = |a: &EntityId, b: &EntityId| if reverse { b.cmp(a) } else { a.cmp(b) };
+                    hierarchy.sort_children_by(id, compare);
+                    if let Some(children) = model.children.get_mut(&id) {
+                        children.sort_by(compare);
+                    }
+                }
+            }
+
+            assert!(hierarchy.repair().is_valid(), "invalid after {:?}", op);
+            let views = (&hierarchy.1, &hierarchy.2);
+            for &id in &ids {
+                assert_eq!(views.children(id).collect::<Vec<_>>(), model.children(id), "children after {:?}", op);
+                assert_eq!(views.ancestors(id).collect::<Vec<_>>(), model.ancestors(id), "ancestors after {:?}", op);
+                assert_eq!(
+                    views.descendants(id).collect::<Vec<_>>(),
+                    model.descendants(id),
+                    "descendants after {:?}",
+                    op
+                );
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn matches_model(ops in prop::collection::vec(op(), 1..64)) {
+            run(&ops);
+        }
+    }
+
+    // 두 자식 링에서 첫번째 자식 떼어내기
+    #[test]
+    fn detach_first_of_two() {
+        run(&[Op::Attach(1, 0), Op::Attach(2, 0), Op::Detach(1), Op::Attach(3, 0), Op::Sort(0, true)]);
+    }
+
+    // 첫번째 자식이 다른 곳으로 옮겨진 부모 제거
+    #[test]
+    fn remove_parent_after_first_child_moved() {
+        run(&[Op::Attach(1, 0), Op::Attach(2, 0), Op::Attach(3, 0), Op::Attach(1, 4), Op::Remove(0), Op::Attach(0, 2)]);
+    }
+}
@@ -1,304 +1,8 @@
-use super::Pos;
-use shipyard::*;
-use std::cmp::PartialOrd;
-
-// 부모 컴포넌트
-#[derive(Component)]
-struct Parent {
-    num_children: usize, // 자식 갯수
-    first_child: EntityId,// 첫번째 자식 엔티티 ID
-}
-
-// 자식 컴포넌트
-#[derive(Component)]
-struct Child {
-    parent: EntityId,
-    prev: EntityId, // 형제 체인을 원형으로 만들어 옵션을 피함
-    next: EntityId,
-}
-
-/*
-!!! 엔티티 !!!
-부모 엔티티, 자식 엔티티라는 용어를 쓰기도 한다.
-하지만 이는 EOS가 아닌 객체 지향적인 개념이며 잘못된 표현이다.
-
-엔티티는 오직 ID이다. 부모 컴포넌트는 자식 엔티티 ID를 값으로 가지고 있는 컴포넌트일 뿐이다.
-자식 컴포넌트 역시 부모와 형제들 엔티티 ID를 가지고 있는 컴포넌트일 뿐이다.
-
-부모 엔티티, 자식 엔티티라고 표현하는 것은 개발자가 해당 ID를 부모, 자식 개념으로 바라보는 것이다.
-엔티티는 절대 클래스나 인스턴스가 아닌 단순한 ID이다.
-
-만약 표현한다면 부모, 자식으로 표현 하는 것이 맞을 것이다.
-부모를 만들기 위해 부모 컴포넌트가 필요하며 부모 엔티티 ID가 할당된다.
-*/
-
-trait Hierarchy {
-    // Removes the child status of an entity.
-    fn detach(&mut self, id: EntityId);
-    // Attaches an entity as a child to a given parent entity.
-    fn attach(&mut self, id: EntityId, parent: EntityId);
-    fn attach_new(&mut self, parent: EntityId) -> EntityId;
-    fn remove(&mut self, id: EntityId);
-    fn remove_all(&mut self, id: EntityId);
-    fn sort_children_by<F>(&mut self, id: EntityId, compare: F)
-        where
-            F: FnMut(&EntityId, &EntityId) -> std::cmp::Ordering;
-}
-
-impl Hierarchy for (EntitiesViewMut<'_>, ViewMut<'_, Parent>, ViewMut<'_, Child>) {
-    fn detach(&mut self, id: EntityId) {
-        let (
-            _,
-            parents,
-            children
-        ) = self;
-
-        // 자식 컴포넌트 제거
-        if let Some(child) = children.remove(id) {
-            // 부모 컴포넌트에서 자식 갯수 1나 감소
-            let parent = &mut parents[child.parent];
-            parent.num_children -= 1;
-
-            if parent.num_children == 0 {
-                // 자식 없으면 부모 컴포넌트도 제거
-                parents.remove(child.parent);
-            } else {
-                if parent.first_child == id {
-                    // 삭제된 자식이 첫번쨰이면 다음 자식을 첫번째로 설정
-                    parent.first_child = child.next;
-                }
-                // 삭제된 자식을 제외하는 링크 결함
-                children[child.prev].next = child.next;
-                children[child.next].prev = child.prev;
-            }
-        }
-    }
-    fn attach(&mut self, id: EntityId, parent: EntityId) {
-        // 기존 링크 관계 제거
-        self.detach(id);
-
-        let (
-            entities,
-            parents,
-            children
-        ) = self;
-
-        if let Ok(mut p) = parents.get(parent) {
-            //부모에 자식 추가
-            p.num_children += 1;
-
-            let prev = children[p.first_child].prev;
-            let next = p.first_child;
-
-            children[prev].next = id;
-            children[next].prev = id;
-
-            entities.add_component(id, children, Child { parent, prev, next });
-        } else {
-            // 새로 부모 추가 + 부모에 자식 추가
-            entities.add_component(
-                id,
-                children,
-                Child {
-                    parent,
-                    prev: id,
-                    next: id,
-                },
-            );
-            entities.add_component(
-                parent,
-                parents,
-                Parent {
-                    num_children: 1,
-                    first_child: id,
-                },
-            );
-        }
-    }
-    fn attach_new(&mut self, parent: EntityId) -> EntityId {
-        // 부모에 자식 생성하여 추가
-        let id = self.0.add_entity((), ());
-        self.attach(id, parent);
-        id
-    }
-    fn remove(&mut self, id: EntityId) {
-        // 자식 링크 제거
-        self.detach(id);
-
-        // 형제와 형제의 자식들
-        let children = (&self.1, &self.2).children(id).collect::<Vec<_>>();
-        for child_id in children {
-            self.detach(child_id);
-        }
-        self.1.remove(id);
-    }
-    fn remove_all(&mut self, id: EntityId) {
-        let (
-            _,
-            parents,
-            children
-        ) = self;
-
-        for child_id in (&*parents, &*children).children(id).collect::<Vec<_>>() {
-            self.remove_all(child_id);
-        }
-        self.remove(id);
-    }
-    fn sort_children_by<F>(&mut self, id: EntityId, compare: F)
-        where
-            F: FnMut(&EntityId, &EntityId) -> std::cmp::Ordering,
-    {
-        let (
-            _,
-            parents,
-            children_storage
-        ) = self;
-
-        let mut children = (&*parents, &*children_storage)
-            .children(id)
-            .collect::<Vec<EntityId>>();
-        if children.len() > 1 {
-            children.sort_by(compare);
-            // set first_child in Parent component
-            parents[id].first_child = children[0];
-            // loop through children and relink them
-            for i in 0..children.len() - 1 {
-                children_storage[children[i]].next = children[i + 1];
-                children_storage[children[i + 1]].prev = children[i];
-            }
-            children_storage[children[0]].prev = *children.last().unwrap();
-            children_storage[*children.last().unwrap()].next = children[0];
-        }
-    }
-}
-
-// 자식들 열거자
-struct ChildrenIter<C> {
-    get_child: C,// 자식 View로 설정
-    cursor: (EntityId, usize),// (첫번째 자식 Entity ID, 자식 갯수)로 설정
-}
-impl<'a, C> Iterator for ChildrenIter<C>
-    where
-        C: Get<Out = &'a Child> + Copy,
-{
-    type Item = EntityId;// 항목은 EntityId
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.cursor.1 > 0 { // 자식 갯수
-            self.cursor.1 -= 1;
-            let ret = self.cursor.0;// 해당 Entity ID 반환
-            self.cursor.0 = self.get_child.get(self.cursor.0).unwrap().next;//
-            Some(ret)
-        } else {
-            None
-        }
-    }
-}
-
-struct AncestorIter<C> {
-    get_child: C,// 자식 View로 설정
-    cursor: EntityId,// 현재 Entity ID로 설정
-}
-
-impl<'a, C> Iterator for AncestorIter<C>
-    where
-        C: Get<Out = &'a Child> + Copy,
-{
-    type Item = EntityId;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.get_child.get(self.cursor).ok().map(|child| {
-            self.cursor = child.parent;
-            child.parent
-        })
-    }
-}
-
-struct DescendantsIter<P, C> {
-    get_parent: P,// 부모 View로 설정
-    get_child: C,// 자식 View로 설정
-    cursors: Vec<(EntityId, usize)>,// [(parent.first_child, parent.num_children)]로 설정
-}
-
-impl<'a, P, C> Iterator for DescendantsIter<P, C>
-    where
-        P: Get<Out = &'a Parent> + Copy,
-        C: Get<Out = &'a Child> + Copy,
-{
-    type Item = EntityId;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(cursor) = self.cursors.last_mut() {
-            if cursor.1 > 0 {
-                cursor.1 -= 1;
-                let ret = cursor.0;
-                cursor.0 = self.get_child.get(cursor.0).unwrap().next;
-                if let Ok(parent) = self.get_parent.get(ret) {
-                    self.cursors.push((parent.first_child, parent.num_children));
-                }
-                Some(ret)
-            } else {
-                self.cursors.pop();
-                self.next()
-            }
-        } else {
-            None
-        }
-    }
-}
-
-trait HierarchyIter<'a, P, C> {
-    fn ancestors(&self, id: EntityId) -> AncestorIter<C>;//조상들
-    fn children(&self, id: EntityId) -> ChildrenIter<C>;//자식들
-    fn descendants(&self, id: EntityId) -> DescendantsIter<P, C>;//자손들
-}
-
-// P : 부모
-// C : 자식
-// (P, C) : P, C 형태의 튜플에 대한 구현
-impl<'a, P, C> HierarchyIter<'a, P, C> for (P, C)
-    where
-        P: Get<Out = &'a Parent> + Copy,
-        C: Get<Out = &'a Child> + Copy,
-{
-    fn ancestors(&self, id: EntityId) -> AncestorIter<C> {
-        let (_, children) = self;
-
-        AncestorIter {
-            get_child: *children,
-            cursor: id,
-        }
-    }
-
-    fn children(&self, id: EntityId) -> ChildrenIter<C> {
-        let (parents, children) = self;
-
-        ChildrenIter {
-            get_child: *children,
-            cursor: parents
-                .get(id)
-                .map_or((id, 0), |parent| (parent.first_child, parent.num_children)),
-        }
-    }
-
-    fn descendants(&self, id: EntityId) -> DescendantsIter<P, C> {
-        let (parents, children) = self;
-
-        DescendantsIter {
-            get_parent: *parents,
-            get_child: *children,
-            cursors: parents.get(id).map_or_else(
-                |_| Vec::new(),
-                |parent| vec![(parent.first_child, parent.num_children)],
-            ),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use crate::*;
-    use crate::hierarchy_test::*;
+    use crate::hierarchy::*;
+    use std::marker::PhantomData;
 
     #[test]
     fn basic() {
@@ -311,12 +15,12 @@ mod tests {
         let root1 = hierarchy.0.add_entity((), ());
         let root2 = hierarchy.0.add_entity((), ());
 
-        let e1 = hierarchy.attach_new(root1);
-        let _e2 = hierarchy.attach_new(e1);
-        let e3 = hierarchy.attach_new(e1);
-        let _e4 = hierarchy.attach_new(e3);
+        let e1 = hierarchy.attach_new(root1).unwrap();
+        let _e2 = hierarchy.attach_new(e1).unwrap();
+        let e3 = hierarchy.attach_new(e1).unwrap();
+        let _e4 = hierarchy.attach_new(e3).unwrap();
 
-        hierarchy.attach(e3, root2);
+        hierarchy.attach(e3, root2).unwrap();
     }
     #[test]
     fn test_hierarchy() {
@@ -329,14 +33,14 @@ mod tests {
         let root1 = hierarchy.0.add_entity((), ());
         let root2 = hierarchy.0.add_entity((), ());
 
-        let e1 = hierarchy.attach_new(root1);
-        let e2 = hierarchy.attach_new(e1);
-        let e3 = hierarchy.attach_new(e1);
-        let e4 = hierarchy.attach_new(e3);
+        let e1 = hierarchy.attach_new(root1).unwrap();
+        let e2 = hierarchy.attach_new(e1).unwrap();
+        let e3 = hierarchy.attach_new(e1).unwrap();
+        let e4 = hierarchy.attach_new(e3).unwrap();
 
-        hierarchy.attach(e3, root2);
+        hierarchy.attach(e3, root2).unwrap();
 
-        let e5 = hierarchy.attach_new(e3);
+        let e5 = hierarchy.attach_new(e3).unwrap();
 
         assert!((&hierarchy.1, &hierarchy.2)
             .children(e3)
@@ -373,6 +77,520 @@ mod tests {
         assert!((&hierarchy.1, &hierarchy.2).ancestors(e5).eq(None));
     }
 
+    #[test]
+    fn test_attach_cycle() {
+        let world = World::new();
+
+        let mut hierarchy = world
+            .borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>)>()
+            .unwrap();
+
+        let root = hierarchy.0.add_entity((), ());
+        let e1 = hierarchy.attach_new(root).unwrap();
+        let e2 = hierarchy.attach_new(e1).unwrap();
+        let e3 = hierarchy.attach_new(e1).unwrap();
+
+        assert_eq!(hierarchy.attach(root, e2), Err(HierarchyError::WouldCreateCycle));
+        assert_eq!(hierarchy.attach(e1, e1), Err(HierarchyError::SelfParent));
+
+        // 실패한 attach는 아무것도 바꾸지 않는다
+        assert!((&hierarchy.1, &hierarchy.2)
+            .descendants(root)
+            .eq([e1, e2, e3].iter().cloned()));
+        assert!((&hierarchy.1, &hierarchy.2).ancestors(root).eq(None));
+
+        hierarchy.0.delete_unchecked(e3);
+        assert_eq!(hierarchy.attach(e3, root), Err(HierarchyError::DeadEntity));
+        assert_eq!(hierarchy.attach_new(e3), Err(HierarchyError::DeadEntity));
+
+        // 형제 밑으로 옮기는 것은 가능
+        hierarchy.detach(e3);
+        let e4 = hierarchy.attach_new(root).unwrap();
+        hierarchy.attach(e2, e4).unwrap();
+        assert!((&hierarchy.1, &hierarchy.2)
+            .ancestors(e2)
+            .eq([e4, root].iter().cloned()));
+    }
+
+    #[test]
+    fn test_validate_and_repair() {
+        let world = World::new();
+
+        let (root, e1, e2, e3) = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root = hierarchy.0.add_entity((), ());
+                let e1 = hierarchy.attach_new(root).unwrap();
+                let e2 = hierarchy.attach_new(root).unwrap();
+                let e3 = hierarchy.attach_new(root).unwrap();
+                (root, e1, e2, e3)
+            },
+        );
+
+        world.run(|parents: View<Parent>, children: View<Child>| {
+            assert!(validate_hierarchy(&parents, &children).is_valid());
+        });
+
+        // 부분 삭제로 e2 의 Child 만 사라진 상황
+        world.run(|mut children: ViewMut<Child>| {
+            children.remove(e2);
+        });
+
+        world.run(|parents: View<Parent>, children: View<Child>| {
+            let report = validate_hierarchy(&parents, &children);
+            assert!(report.violations().contains(&HierarchyViolation::BrokenLink { id: e1, next: e2 }));
+            assert!(report.violations().contains(&HierarchyViolation::NotInRing { child: e3, parent: root }));
+            assert!(report.violations().contains(&HierarchyViolation::ChildCountMismatch {
+                parent: root,
+                num_children: 3,
+                ring_len: 1,
+            }));
+        });
+
+        world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut hierarchy = (entities, parents, children);
+
+                assert!(!hierarchy.repair().is_valid());
+                assert!(hierarchy.repair().is_valid());
+                assert!((&hierarchy.1, &hierarchy.2)
+                    .children(root)
+                    .eq([e1, e3].iter().cloned()));
+            },
+        );
+
+        world.run(|parents: View<Parent>, children: View<Child>| {
+            assert!(validate_hierarchy(&parents, &children).is_valid());
+        });
+    }
+
+    #[test]
+    fn test_cycle() {
+        let world = World::new();
+
+        // a 가 b 의 부모이면서 b 의 자식인 상황. 링은 모두 올바르다.
+        let (a, b) = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let a = hierarchy.0.add_entity((), ());
+                let b = hierarchy.attach_new(a).unwrap();
+                hierarchy.0.add_component(
+                    a,
+                    &mut hierarchy.2,
+                    Child { parent: b, prev: a, next: a, relation: PhantomData },
+                );
+                hierarchy.0.add_component(
+                    b,
+                    &mut hierarchy.1,
+                    Parent { num_children: 1, first_child: a, relation: PhantomData },
+                );
+                (a, b)
+            },
+        );
+
+        world.run(|parents: View<Parent>, children: View<Child>| {
+            let report = validate_hierarchy(&parents, &children);
+            assert_eq!(report.violations(), &[HierarchyViolation::Cycle { entity: a.min(b) }]);
+        });
+
+        world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut hierarchy = (entities, parents, children);
+
+                assert!(!hierarchy.repair().is_valid());
+                assert!(hierarchy.repair().is_valid());
+                let views = (&hierarchy.1, &hierarchy.2);
+                let root = a.min(b);
+                let other = a.max(b);
+                assert_eq!(views.root_of(other), root);
+                assert!(views.ancestors(root).next().is_none());
+                assert!(views.descendants(root).eq([other].iter().cloned()));
+            },
+        );
+    }
+
+    #[test]
+    fn test_cleanup_on_delete() {
+        let mut world = World::new();
+        world.add_unique(OrphanPolicy::Reparent);
+        world.add_workload(hierarchy_cleanup_workload::<DefaultRelation>);
+
+        let (root, e1, e2, e3, e4) = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root = hierarchy.0.add_entity((), ());
+                let e1 = hierarchy.attach_new(root).unwrap();
+                let e2 = hierarchy.attach_new(e1).unwrap();
+                let e3 = hierarchy.attach_new(e1).unwrap();
+                let e4 = hierarchy.attach_new(root).unwrap();
+                (root, e1, e2, e3, e4)
+            },
+        );
+
+        world.delete_entity(e1);
+        world.run_workload(hierarchy_cleanup_workload::<DefaultRelation>).unwrap();
+
+        world.run(|parents: View<Parent>, children: View<Child>| {
+            assert!(validate_hierarchy(&parents, &children).is_valid());
+            assert!((&parents, &children)
+                .children(root)
+                .eq([e4, e2, e3].iter().cloned()));
+        });
+
+        world.run(|mut policy: UniqueViewMut<OrphanPolicy>| {
+            *policy = OrphanPolicy::Cascade;
+        });
+        let e5 = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                (entities, parents, children).attach_new(e2).unwrap()
+            },
+        );
+
+        world.delete_entity(root);
+        world.run_workload(hierarchy_cleanup_workload::<DefaultRelation>).unwrap();
+
+        world.run(|entities: EntitiesView, parents: View<Parent>, children: View<Child>| {
+            assert!(validate_hierarchy(&parents, &children).is_valid());
+            for id in [e2, e3, e4, e5] {
+                assert!(!entities.is_alive(id));
+            }
+            assert_eq!(children.iter().count(), 0);
+        });
+    }
+
+    #[test]
+    fn test_traversal_orders() {
+        let world = World::new();
+
+        let mut hierarchy = world
+            .borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>)>()
+            .unwrap();
+
+        // root
+        // ├ e1
+        // │ ├ e3
+        // │ │ └ e5
+        // │ └ e4
+        // └ e2
+        let root = hierarchy.0.add_entity((), ());
+        let e1 = hierarchy.attach_new(root).unwrap();
+        let e2 = hierarchy.attach_new(root).unwrap();
+        let e3 = hierarchy.attach_new(e1).unwrap();
+        let e4 = hierarchy.attach_new(e1).unwrap();
+        let e5 = hierarchy.attach_new(e3).unwrap();
+
+        let views = (&hierarchy.1, &hierarchy.2);
+
+        assert!(views
+            .descendants_bfs(root)
+            .eq([e1, e2, e3, e4, e5].iter().cloned()));
+        assert!(views
+            .descendants_post_order(root)
+            .eq([e5, e3, e4, e1, e2].iter().cloned()));
+        assert!(views
+            .descendants_with_depth(root, usize::MAX)
+            .eq([(e1, 1), (e3, 2), (e5, 3), (e4, 2), (e2, 1)].iter().cloned()));
+        assert!(views
+            .descendants_with_depth(root, 2)
+            .eq([(e1, 1), (e3, 2), (e4, 2), (e2, 1)].iter().cloned()));
+        assert!(views.descendants_with_depth(root, 0).eq(None));
+
+        assert!(views.descendants_bfs(e2).eq(None));
+        assert!(views.descendants_post_order(e2).eq(None));
+    }
+
+    #[test]
+    fn test_ordered_insertion() {
+        let world = World::new();
+
+        let mut hierarchy = world
+            .borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>)>()
+            .unwrap();
+
+        let root = hierarchy.0.add_entity((), ());
+        let a = hierarchy.attach_new(root).unwrap();
+        let b = hierarchy.attach_new(root).unwrap();
+        let c = hierarchy.attach_new(root).unwrap();
+        let d = hierarchy.0.add_entity((), ());
+        let e = hierarchy.0.add_entity((), ());
+
+        hierarchy.insert_child_at(root, 0, d).unwrap();
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(root)
+            .eq([d, a, b, c].iter().cloned()));
+
+        hierarchy.insert_before(b, e).unwrap();
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(root)
+            .eq([d, a, e, b, c].iter().cloned()));
+
+        hierarchy.insert_after(c, d).unwrap();
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(root)
+            .eq([a, e, b, c, d].iter().cloned()));
+
+        hierarchy.move_child(root, 4, 1).unwrap();
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(root)
+            .eq([a, d, e, b, c].iter().cloned()));
+
+        hierarchy.swap_siblings(a, c).unwrap();
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(root)
+            .eq([c, d, e, b, a].iter().cloned()));
+
+        let g = hierarchy.0.add_entity((), ());
+        assert_eq!(hierarchy.insert_child_at(root, 6, g), Err(HierarchyError::IndexOutOfBounds));
+        assert_eq!(hierarchy.move_child(root, 0, 5), Err(HierarchyError::IndexOutOfBounds));
+        assert_eq!(hierarchy.insert_after(root, a), Err(HierarchyError::NotAChild));
+        let f = hierarchy.attach_new(a).unwrap();
+        assert_eq!(hierarchy.swap_siblings(b, f), Err(HierarchyError::NotSiblings));
+
+        assert!(hierarchy.repair().is_valid());
+    }
+
+    #[derive(Component)]
+    struct Target(EntityId);
+
+    impl CloneComponent for Target {
+        fn clone_mapped(&self, map: &EntityMap) -> Self {
+            Target(map.get(self.0))
+        }
+    }
+
+    impl CloneComponent for Pos {
+        fn clone_mapped(&self, _: &EntityMap) -> Self {
+            Pos(self.0, self.1)
+        }
+    }
+
+    #[derive(Component)]
+    struct Unregistered;
+
+    #[test]
+    fn test_clone_subtree() {
+        let world = World::new();
+        let mut registry = CloneRegistry::default();
+        registry.register::<Pos>();
+        registry.register::<Target>();
+        world.add_unique(registry);
+
+        let (mut hierarchy, mut vm_pos, mut vm_target, mut vm_unregistered) = world
+            .borrow::<(
+                (EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>),
+                ViewMut<Pos>,
+                ViewMut<Target>,
+                ViewMut<Unregistered>,
+            )>()
+            .unwrap();
+
+        let army = hierarchy.0.add_entity((), ());
+        let base = hierarchy.0.add_entity((), ());
+        let squad = hierarchy.attach_new(army).unwrap();
+        let leader = hierarchy.attach_new(squad).unwrap();
+        let member = hierarchy.attach_new(squad).unwrap();
+        let scout = hierarchy.attach_new(member).unwrap();
+
+        hierarchy.0.add_component(leader, &mut vm_pos, Pos(1, 2));
+        hierarchy.0.add_component(member, (&mut vm_pos, &mut vm_target), (Pos(3, 4), Target(leader)));
+        hierarchy.0.add_component(scout, (&mut vm_target, &mut vm_unregistered), (Target(base), Unregistered));
+        drop((hierarchy, vm_pos, vm_target, vm_unregistered));
+
+        let copy = world
+            .run(|mut all_storages: AllStoragesViewMut| clone_subtree::<DefaultRelation>(&mut all_storages, squad, army))
+            .unwrap();
+
+        let (mut hierarchy, vm_pos, vm_target, vm_unregistered) = world
+            .borrow::<(
+                (EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>),
+                View<Pos>,
+                View<Target>,
+                View<Unregistered>,
+            )>()
+            .unwrap();
+
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(army)
+            .eq([squad, copy].iter().cloned()));
+
+        let copies = (&hierarchy.1, &hierarchy.2).descendants(copy).collect::<Vec<_>>();
+        assert_eq!(copies.len(), 3);
+        let (copy_leader, copy_member, copy_scout) = (copies[0], copies[1], copies[2]);
+        assert!((&hierarchy.1, &hierarchy.2)
+            .ancestors(copy_scout)
+            .eq([copy_member, copy, army].iter().cloned()));
+
+        assert_eq!((vm_pos[copy_leader].0, vm_pos[copy_leader].1), (1, 2));
+        assert_eq!((vm_pos[copy_member].0, vm_pos[copy_member].1), (3, 4));
+        // 하위 트리 안은 복사본을 가리키고 밖은 그대로
+        assert_eq!(vm_target[copy_member].0, copy_leader);
+        assert_eq!(vm_target[copy_scout].0, base);
+        assert_eq!(vm_target[member].0, leader);
+        // 등록하지 않은 컴포넌트는 복사되지 않는다
+        assert!(vm_unregistered.contains(scout) && !vm_unregistered.contains(copy_scout));
+
+        // 복제 대상 안으로 복제해도 한 번만 복제된다
+        assert!(hierarchy.copy_links(squad, scout).is_ok());
+        assert!(hierarchy.repair().is_valid());
+    }
+
+    #[test]
+    fn test_events() {
+        let world = World::new();
+        world.add_unique(HierarchyEvents::<DefaultRelation>::default());
+
+        let mut hierarchy = world
+            .borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>, UniqueViewMut<HierarchyEvents>)>()
+            .unwrap();
+
+        let root1 = hierarchy.0.add_entity((), ());
+        let root2 = hierarchy.0.add_entity((), ());
+        let e1 = hierarchy.attach_new(root1).unwrap();
+        let e2 = hierarchy.attach_new(root1).unwrap();
+
+        assert_eq!(
+            hierarchy.3.drain().collect::<Vec<_>>(),
+            vec![
+                HierarchyEvent::Attached { child: e1, parent: root1 },
+                HierarchyEvent::Attached { child: e2, parent: root1 },
+            ]
+        );
+
+        // 순서 변경과 부모 변경을 구분할 수 있다
+        hierarchy.swap_siblings(e1, e2).unwrap();
+        hierarchy.attach(e1, root2).unwrap();
+        hierarchy.remove(root1);
+
+        assert_eq!(
+            hierarchy.3.drain().collect::<Vec<_>>(),
+            vec![
+                HierarchyEvent::Reordered { parent: root1 },
+                HierarchyEvent::Detached { child: e1, old_parent: root1 },
+                HierarchyEvent::Attached { child: e1, parent: root2 },
+                HierarchyEvent::Detached { child: e2, old_parent: root1 },
+                HierarchyEvent::Removed { id: root1 },
+            ]
+        );
+
+        // 실패한 변경은 이벤트를 남기지 않는다
+        assert!(hierarchy.attach(root2, e1).is_err());
+        assert_eq!(hierarchy.3.drain().count(), 0);
+
+        // 계층에 속하지 않은 엔티티를 제거해도 이벤트가 없다
+        let lone = hierarchy.0.add_entity((), ());
+        hierarchy.remove(lone);
+        hierarchy.remove(root1);
+        assert_eq!(hierarchy.3.drain().count(), 0);
+    }
+
+    #[test]
+    fn test_queries() {
+        let world = World::new();
+
+        let mut hierarchy = world
+            .borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>)>()
+            .unwrap();
+
+        // vehicle
+        // ├ body
+        // │ ├ door
+        // │ └ hood
+        // └ wheel
+        let vehicle = hierarchy.0.add_entity((), ());
+        let body = hierarchy.attach_new(vehicle).unwrap();
+        let wheel = hierarchy.attach_new(vehicle).unwrap();
+        let door = hierarchy.attach_new(body).unwrap();
+        let hood = hierarchy.attach_new(body).unwrap();
+        let rock = hierarchy.0.add_entity((), ());
+
+        let views = (&hierarchy.1, &hierarchy.2);
+
+        assert_eq!(views.root_of(door), vehicle);
+        assert_eq!(views.root_of(vehicle), vehicle);
+        assert_eq!(views.depth(vehicle), 0);
+        assert_eq!(views.depth(hood), 2);
+
+        assert!(views.is_ancestor_of(vehicle, hood));
+        assert!(!views.is_ancestor_of(hood, vehicle));
+        assert!(!views.is_ancestor_of(door, door));
+
+        assert_eq!(views.lowest_common_ancestor(door, hood), Some(body));
+        assert_eq!(views.lowest_common_ancestor(door, wheel), Some(vehicle));
+        assert_eq!(views.lowest_common_ancestor(body, hood), Some(body));
+        assert_eq!(views.lowest_common_ancestor(door, rock), None);
+
+        assert!(views.siblings(body).eq([wheel].iter().cloned()));
+        assert!(views.siblings(hood).eq([door].iter().cloned()));
+        assert!(views.siblings(vehicle).eq(None));
+
+        assert_eq!(views.path_between(door, wheel), Some(vec![door, body, vehicle, wheel]));
+        assert_eq!(views.path_between(hood, body), Some(vec![hood, body]));
+        assert_eq!(views.path_between(door, door), Some(vec![door]));
+        assert_eq!(views.path_between(door, rock), None);
+    }
+
+    #[derive(Component, PartialEq, Eq, PartialOrd, Ord)]
+    struct ZOrder(i32);
+
+    #[test]
+    fn test_sorting_levels() {
+        let world = World::new();
+
+        let (mut hierarchy, mut vm_z) = world
+            .borrow::<(
+                (EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>),
+                ViewMut<ZOrder>,
+            )>()
+            .unwrap();
+
+        let root = hierarchy.0.add_entity((), ());
+        let a = hierarchy.attach_new(root).unwrap();
+        let b = hierarchy.attach_new(root).unwrap();
+        let c = hierarchy.attach_new(root).unwrap();
+        let a1 = hierarchy.attach_new(a).unwrap();
+        let a2 = hierarchy.attach_new(a).unwrap();
+        let a3 = hierarchy.attach_new(a).unwrap();
+
+        hierarchy.0.add_component(a, &mut vm_z, ZOrder(2));
+        hierarchy.0.add_component(b, &mut vm_z, ZOrder(1));
+        hierarchy.0.add_component(a1, &mut vm_z, ZOrder(5));
+        hierarchy.0.add_component(a2, &mut vm_z, ZOrder(5));
+        hierarchy.0.add_component(a3, &mut vm_z, ZOrder(0));
+
+        hierarchy.sort_descendants_by_component(root, &vm_z);
+
+        // c 는 ZOrder 가 없어서 뒤로, a1 과 a2 는 같은 값이라 순서 유지
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(root)
+            .eq([b, a, c].iter().cloned()));
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(a)
+            .eq([a3, a1, a2].iter().cloned()));
+
+        hierarchy.sort_children_by_key(a, |id| if *id == a2 { 0 } else { 1 });
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(a)
+            .eq([a2, a3, a1].iter().cloned()));
+
+        hierarchy.sort_descendants_by(root, |x, y| y.cmp(x));
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(root)
+            .eq([c, b, a].iter().cloned()));
+        assert!((&hierarchy.1, &hierarchy.2)
+            .children(a)
+            .eq([a3, a2, a1].iter().cloned()));
+        drop((hierarchy, vm_z));
+
+        // World 에서는 컴포넌트 타입만 지정한다
+        world.sort_children_by_component::<ZOrder>(a);
+        assert_eq!(world.children(a), vec![a3, a2, a1]);
+        world.sort_descendants_by_component::<ZOrder>(root);
+        assert_eq!(world.children(root), vec![b, a, c]);
+    }
+
     #[test]
     fn test_sorting() {
         let world = World::new();
@@ -386,11 +604,11 @@ mod tests {
 
         let root = hierarchy.0.add_entity((), ());
 
-        let e0 = hierarchy.attach_new(root);
-        let e1 = hierarchy.attach_new(root);
-        let e2 = hierarchy.attach_new(root);
-        let e3 = hierarchy.attach_new(root);
-        let e4 = hierarchy.attach_new(root);
+        let e0 = hierarchy.attach_new(root).unwrap();
+        let e1 = hierarchy.attach_new(root).unwrap();
+        let e2 = hierarchy.attach_new(root).unwrap();
+        let e3 = hierarchy.attach_new(root).unwrap();
+        let e4 = hierarchy.attach_new(root).unwrap();
 
         hierarchy.0.add_component(e0, &mut vm_pos, Pos(7, 0));
         hierarchy.0.add_component(e1, &mut vm_pos, Pos(5, 0));
@@ -410,4 +628,193 @@ mod tests {
             .children(root)
             .eq([e3, e4, e1, e2, e0].iter().cloned()));
     }
-}
\ No newline at end of file
+
+    // 장면 트리와 별개인 소지품 트리
+    struct Inventory;
+
+    impl Relation for Inventory {}
+
+    #[test]
+    fn test_relations() {
+        let world = World::new();
+
+        // 장면: room ─ player, chest, sword, potion
+        let (room, player, chest, sword, potion) = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                let mut scene = (entities, parents, children);
+
+                let room = scene.0.add_entity((), ());
+                let player = scene.attach_new(room).unwrap();
+                let chest = scene.attach_new(room).unwrap();
+                let sword = scene.attach_new(room).unwrap();
+                let potion = scene.attach_new(room).unwrap();
+                (room, player, chest, sword, potion)
+            },
+        );
+
+        // 소지품: player ─ sword, potion / chest 는 비어 있음
+        world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent<Inventory>>, children: ViewMut<Child<Inventory>>| {
+                let mut inventory = (entities, parents, children);
+
+                inventory.attach(sword, player).unwrap();
+                inventory.attach(potion, player).unwrap();
+                inventory.sort_children_by(player, |a, b| b.cmp(a));
+                assert_eq!(inventory.attach(player, sword), Err(HierarchyError::WouldCreateCycle));
+
+                assert!((&inventory.1, &inventory.2).children(player).eq([potion, sword].iter().cloned()));
+                assert!((&inventory.1, &inventory.2).ancestors(sword).eq([player].iter().cloned()));
+            },
+        );
+
+        // 장면 트리는 그대로
+        world.run(|parents: View<Parent>, children: View<Child>| {
+            assert!((&parents, &children)
+                .children(room)
+                .eq([player, chest, sword, potion].iter().cloned()));
+            assert!((&parents, &children).ancestors(sword).eq([room].iter().cloned()));
+            assert!(validate_hierarchy(&parents, &children).is_valid());
+        });
+
+        // 장면에서 떼어내도 소지품 관계는 남는다
+        world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+                (entities, parents, children).detach(sword);
+            },
+        );
+        world.run(|parents: View<Parent<Inventory>>, children: View<Child<Inventory>>| {
+            assert!((&parents, &children).ancestors(sword).eq([player].iter().cloned()));
+            assert!(validate_hierarchy(&parents, &children).is_valid());
+        });
+    }
+
+    #[test]
+    fn test_par_for_each_subtree() {
+        let world = World::new();
+
+        let roots = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>, mut vm_pos: ViewMut<Pos>| {
+                let mut hierarchy = (entities, parents, children);
+
+                // 루트 i 아래에 길이 i + 1 인 사슬
+                let mut roots = Vec::new();
+                for i in 0..64 {
+                    let root = hierarchy.0.add_entity((), ());
+                    let mut last = root;
+                    for _ in 0..=i {
+                        last = hierarchy.attach_new(last).unwrap();
+                        hierarchy.0.add_component(last, &mut vm_pos, Pos::new(1, i));
+                    }
+                    roots.push(root);
+                }
+                // 계층에 속하지 않은 엔티티는 루트가 아니다
+                hierarchy.0.add_entity(&mut vm_pos, Pos::new(1, 0));
+                roots
+            },
+        );
+
+        world.run(|parents: View<Parent>, children: View<Child>, v_pos: View<Pos>| {
+            let results = std::sync::Mutex::new(Vec::new());
+            par_for_each_subtree(&parents, &children, |root, descendants| {
+                let sum = descendants.map(|id| v_pos[id].0).sum::<u32>();
+                results.lock().unwrap().push((root, sum));
+            });
+
+            let mut results = results.into_inner().unwrap();
+            results.sort();
+            assert!(results.iter().map(|(root, _)| *root).eq(roots.iter().cloned()));
+            assert!(results.iter().map(|(_, sum)| *sum).eq(1..=64));
+        });
+    }
+
+    #[test]
+    fn test_transaction() {
+        let world = World::new();
+        world.add_unique(HierarchyEvents::<DefaultRelation>::default());
+
+        let mut hierarchy = world
+            .borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>, UniqueViewMut<HierarchyEvents>)>()
+            .unwrap();
+
+        let root = hierarchy.0.add_entity((), ());
+        let a = hierarchy.attach_new(root).unwrap();
+        let b = hierarchy.attach_new(root).unwrap();
+        let c = hierarchy.attach_new(a).unwrap();
+        let dead = hierarchy.0.add_entity((), ());
+        hierarchy.0.delete_unchecked(dead);
+        assert_eq!(hierarchy.3.drain().count(), 3);
+
+        // 앞의 변경 때문에 생기는 순환도 검출한다: a 가 c 밑으로 가면 c → a 는 순환
+        let result = HierarchyTransaction::new()
+            .detach(c)
+            .attach(a, c)
+            .attach(b, a)
+            .attach(c, b)
+            .apply(&mut hierarchy);
+        assert_eq!(result, Err(TransactionError { index: 3, error: HierarchyError::WouldCreateCycle }));
+
+        let result = HierarchyTransaction::new()
+            .attach(b, a)
+            .attach(c, dead)
+            .apply(&mut hierarchy);
+        assert_eq!(result, Err(TransactionError { index: 1, error: HierarchyError::DeadEntity }));
+
+        // 실패한 트랜잭션은 아무것도 바꾸지 않는다
+        assert!((&hierarchy.1, &hierarchy.2).descendants(root).eq([a, c, b].iter().cloned()));
+        assert_eq!(hierarchy.3.drain().count(), 0);
+
+        HierarchyTransaction::new()
+            .attach(c, root)
+            .attach(b, c)
+            .remove(a)
+            .apply(&mut hierarchy)
+            .unwrap();
+        assert!((&hierarchy.1, &hierarchy.2).descendants(root).eq([c, b].iter().cloned()));
+        assert_eq!(hierarchy.3.drain().count(), 6);
+
+        // 적용 도중 실패하면 이전 상태로 되돌린다
+        let snapshot = LinkSnapshot::take(&mut hierarchy, [root, a, b, c].iter().copied().collect());
+        hierarchy.attach(a, b).unwrap();
+        hierarchy.sort_children_by(root, |x, y| y.cmp(x));
+        snapshot.restore(&mut hierarchy);
+        assert!((&hierarchy.1, &hierarchy.2).descendants(root).eq([c, b].iter().cloned()));
+        assert!(hierarchy.repair().is_valid());
+    }
+
+    struct Ui;
+
+    impl Relation for Ui {}
+
+    #[test]
+    fn world_extension_test() {
+        let world = World::new();
+        world.add_unique(HierarchyEvents::<DefaultRelation>::default());
+
+        let root = world.run(|mut entities: EntitiesViewMut| entities.add_entity((), ()));
+        let e1 = world.attach_new(root).unwrap();
+        let e2 = world.attach_new(e1).unwrap();
+        let e3 = world.attach_new(root).unwrap();
+
+        assert_eq!(world.children(root), vec![e1, e3]);
+        assert_eq!(world.ancestors(e2), vec![e1, root]);
+        assert_eq!(world.descendants(root), vec![e1, e2, e3]);
+        assert_eq!(world.attach(root, e2), Err(HierarchyError::WouldCreateCycle));
+
+        world.attach(e2, e3).unwrap();
+        assert_eq!(world.parent_of(e2), Some(e3));
+        world.run(|mut events: UniqueViewMut<HierarchyEvents>| {
+            assert_eq!(events.drain().count(), 5);
+        });
+
+        // 다른 관계는 hierarchy::<R>() 로 사용한다
+        world.hierarchy::<Ui>().attach(root, e2).unwrap();
+        assert!(world.hierarchy::<Ui>().query().children(e2).eq([root].iter().cloned()));
+        assert_eq!(world.parent_of(root), None);
+
+        world.run(|all_storages: AllStoragesViewMut| {
+            all_storages.remove_all_from_hierarchy(root);
+            assert!(all_storages.descendants(root).is_empty());
+            assert_eq!(all_storages.hierarchy_query::<Ui>().0.len(), 1);
+        });
+    }
+}
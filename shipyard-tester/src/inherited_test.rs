@@ -0,0 +1,190 @@
+use crate::hierarchy::{Child, HierarchyIter, Parent, Relation};
+use shipyard::*;
+use std::collections::HashSet;
+
+pub(crate) trait GetInherited<'a> {
+    // Nearest `T` on `id` itself or on one of its ancestors.
+    fn get_inherited<T: 'a>(&self, components: impl Get<Out = &'a T> + Copy, id: EntityId) -> Option<&'a T>;
+}
+
+impl<'a, R, P, C> GetInherited<'a> for (P, C)
+    where
+        R: Relation,
+        P: Get<Out = &'a Parent<R>> + Copy,
+        C: Get<Out = &'a Child<R>> + Copy,
+{
+    fn get_inherited<T: 'a>(&self, components: impl Get<Out = &'a T> + Copy, id: EntityId) -> Option<&'a T> {
+        std::iter::once(id)
+            .chain(self.ancestors(id))
+            .find_map(|id| components.get(id).ok())
+    }
+}
+
+// 가장 가까운 T 의 복사본. inherited_system 이 관리하므로 직접 수정하지 않는다.
+#[derive(Component, Debug, Clone, PartialEq)]
+pub(crate) struct Inherited<T: Clone + Send + Sync + 'static> {
+    pub(crate) source: EntityId, // T 를 가진 엔티티 (자신일 수 있다)
+    pub(crate) value: T,
+}
+
+// T 가 추가/수정/제거되었거나 R 관계의 부모가 바뀐 엔티티의 하위 트리만 다시 계산한다.
+// 추가/수정/제거를 모두 읽으므로 T 는 #[track(All)] 이어야 한다.
+// 삭제된 엔티티의 Inherited 는 함께 삭제되고 자식들은 정리 시스템이 다시 붙이므로 삭제 기록은 읽지 않는다.
+fn inherited_system<T, R>(
+    entities: EntitiesView,
+    values: View<T>,
+    parents: View<Parent<R>>,
+    children: View<Child<R>>,
+    mut inherited: ViewMut<Inherited<T>>,
+) where
+    T: Component<Tracking = track::All> + Clone + Send + Sync,
+    R: Relation,
+{
+    let hierarchy = (&parents, &children);
+
+    let dirty = values
+        .inserted_or_modified()
+        .iter()
+        .ids()
+        .chain(values.removed())
+        .chain(children.inserted_or_modified().iter().ids())
+        .chain(children.removed_or_deleted())
+        .filter(|id| entities.is_alive(*id))
+        .collect::<HashSet<_>>();
+
+    // 조상이 dirty 이면 조상을 다시 계산할 때 함께 처리된다
+    let mut dirty_roots = dirty
+        .iter()
+        .copied()
+        .filter(|id| !hierarchy.ancestors(*id).any(|ancestor| dirty.contains(&ancestor)))
+        .collect::<Vec<_>>();
+    dirty_roots.sort();
+
+    for root in dirty_roots {
+        // 전위 순회이므로 부모의 Inherited 가 항상 먼저 갱신된다
+        for id in std::iter::once(root).chain(hierarchy.descendants(root)) {
+            let nearest = match values.get(id) {
+                Ok(value) => Some(Inherited { source: id, value: value.clone() }),
+                Err(_) => hierarchy
+                    .ancestors(id)
+                    .next()
+                    .and_then(|parent| inherited.get(parent).ok().cloned()),
+            };
+            match nearest {
+                Some(nearest) => entities.add_component(id, &mut inherited, nearest),
+                None => {
+                    inherited.remove(id);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn inherited_workload<T, R>() -> Workload
+    where
+        T: Component<Tracking = track::All> + Clone + Send + Sync,
+        R: Relation,
+{
+    inherited_system::<T, R>.into_workload()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hierarchy::{DefaultRelation, Hierarchy};
+    use crate::inherited_test::*;
+
+    #[derive(Component, Debug, Clone, PartialEq)]
+    #[track(All)]
+    struct Team(u32);
+
+    struct Ownership;
+
+    impl Relation for Ownership {}
+
+    #[test]
+    fn inherited_test() {
+        let world = World::new();
+        world.add_workload(inherited_workload::<Team, DefaultRelation>);
+
+        // root(1) ─ a ─ b(2) ─ c
+        let (root, a, b, c, lone) = world.run(
+            |entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>, mut vm_team: ViewMut<Team>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root = hierarchy.0.add_entity(&mut vm_team, Team(1));
+                let a = hierarchy.attach_new(root).unwrap();
+                let b = hierarchy.attach_new(a).unwrap();
+                let c = hierarchy.attach_new(b).unwrap();
+                hierarchy.0.add_component(b, &mut vm_team, Team(2));
+                let lone = hierarchy.0.add_entity((), ());
+
+                let views = (&hierarchy.1, &hierarchy.2);
+                assert_eq!(views.get_inherited::<Team>(&vm_team, a), Some(&Team(1)));
+                assert_eq!(views.get_inherited::<Team>(&vm_team, c), Some(&Team(2)));
+                assert_eq!(views.get_inherited::<Team>(&vm_team, lone), None);
+                (root, a, b, c, lone)
+            },
+        );
+
+        world.run_workload(inherited_workload::<Team, DefaultRelation>).unwrap();
+        world.run(|inherited: View<Inherited<Team>>| {
+            assert_eq!(inherited[a], Inherited { source: root, value: Team(1) });
+            assert_eq!(inherited[b], Inherited { source: b, value: Team(2) });
+            assert_eq!(inherited[c], Inherited { source: b, value: Team(2) });
+            assert!(!inherited.contains(lone));
+        });
+
+        // 값 수정과 제거
+        world.run(|mut vm_team: ViewMut<Team>| {
+            (&mut vm_team).get(root).unwrap().0 = 3;
+            vm_team.remove(b);
+        });
+        world.run_workload(inherited_workload::<Team, DefaultRelation>).unwrap();
+        world.run(|inherited: View<Inherited<Team>>| {
+            assert_eq!(inherited[a].value, Team(3));
+            assert_eq!(inherited[c], Inherited { source: root, value: Team(3) });
+        });
+
+        // 트리에서 떼어내면 더 이상 물려받지 않는다
+        world.run(|entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+            (entities, parents, children).detach(a);
+        });
+        world.run_workload(inherited_workload::<Team, DefaultRelation>).unwrap();
+        world.run(|inherited: View<Inherited<Team>>| {
+            assert!(inherited.contains(root));
+            assert!(!inherited.contains(a) && !inherited.contains(b) && !inherited.contains(c));
+        });
+    }
+    #[test]
+    fn relation_test() {
+        let world = World::new();
+        world.add_workload(inherited_workload::<Team, Ownership>);
+
+        // 기본 계층과 상관없이 Ownership 관계로만 물려받는다
+        let (owner, item) = world.run(
+            |entities: EntitiesViewMut,
+             parents: ViewMut<Parent<Ownership>>,
+             children: ViewMut<Child<Ownership>>,
+             mut vm_team: ViewMut<Team>| {
+                let mut ownership = (entities, parents, children);
+
+                let owner = ownership.0.add_entity(&mut vm_team, Team(7));
+                let item = ownership.attach_new(owner).unwrap();
+                (owner, item)
+            },
+        );
+
+        world.run_workload(inherited_workload::<Team, Ownership>).unwrap();
+        world.run(|inherited: View<Inherited<Team>>| {
+            assert_eq!(inherited[item], Inherited { source: owner, value: Team(7) });
+        });
+
+        world.run(|mut vm_team: ViewMut<Team>| {
+            (&mut vm_team).get(owner).unwrap().0 = 8;
+        });
+        world.run_workload(inherited_workload::<Team, Ownership>).unwrap();
+        world.run(|inherited: View<Inherited<Team>>| {
+            assert_eq!(inherited[item].value, Team(8));
+        });
+    }
+}
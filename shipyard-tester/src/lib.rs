@@ -1,14 +1,34 @@
+// 이 크레이트의 타입과 시스템은 대부분 테스트에서만 쓰인다
+#![cfg_attr(not(test), allow(dead_code))]
+
 mod entity_test;
 mod component_test;
 mod control_component_test;
 mod tracking_test;
 mod workload_test;
 mod hierarchy_test;
+mod transform_test;
+mod serialize_test;
+mod flat_hierarchy_test;
+mod hierarchy_dump_test;
+mod hierarchy_prop_test;
+mod inherited_test;
+mod flag_propagation_test;
+mod spatial_index_test;
+mod physics_test;
+mod workload_report_test;
+#[cfg(feature = "profiler")]
+mod profiler_test;
+mod commands_test;
+
+pub mod hierarchy;
 
+use serde::{Deserialize, Serialize};
 use shipyard::*;
 use std::collections::HashMap;
 
-#[derive(Component, Debug)]
+#[derive(Component, Debug, Serialize, Deserialize)]
+#[track(All)]
 struct Pos(u32, u32);
 impl Pos {
     fn new(x: u32, y: u32) -> Pos {
@@ -16,11 +36,12 @@ impl Pos {
     }
 }
 
-#[derive(Component, Debug)]
-struct Vel(u32);
+// 고정 시간 간격 한 번에 이동하는 거리 (x, y)
+#[derive(Component, Debug, Serialize, Deserialize)]
+struct Vel(i32, i32);
 impl Vel {
-    fn new(velocity: u32) -> Vel {
-        Vel(velocity)
+    fn new(x: i32, y: i32) -> Vel {
+        Vel(x, y)
     }
 }
 
@@ -79,7 +100,7 @@ fn read_only_system_2(
 }
 
 fn read_write_system(mut view_vel: ViewMut<Vel>) {
-    for mut vel in (&mut view_vel).iter() {
+    for vel in (&mut view_vel).iter() {
         vel.0 = 10;
         println!("X : vel : {}", vel.0);
     }
@@ -98,9 +119,9 @@ fn not_parallel_workload() -> Workload {
 
 pub fn outer_parallel_able_test() {
     let mut world = World::new();
-    world.add_entity((Vel::new(0), Pos::new(0, 0)));
-    world.add_entity((Vel::new(1), Pos::new(1, 1)));
-    world.add_entity((Vel::new(2), Pos::new(2, 2)));
+    world.add_entity((Vel::new(0, 0), Pos::new(0, 0)));
+    world.add_entity((Vel::new(1, 0), Pos::new(1, 1)));
+    world.add_entity((Vel::new(2, 0), Pos::new(2, 2)));
     world.add_workload(parallel_workload);
     world.run_workload(parallel_workload).unwrap();
     // X : vel : 0
@@ -116,9 +137,9 @@ pub fn outer_parallel_not_able_test() {
     // - There can't be any other access during an exclusive access,
     // so ViewMut<T> will block T threading.
     let mut world = World::new();
-    world.add_entity((Vel::new(0), Pos::new(0, 0)));
-    world.add_entity((Vel::new(1), Pos::new(1, 1)));
-    world.add_entity((Vel::new(2), Pos::new(2, 2)));
+    world.add_entity((Vel::new(0, 0), Pos::new(0, 0)));
+    world.add_entity((Vel::new(1, 0), Pos::new(1, 1)));
+    world.add_entity((Vel::new(2, 0), Pos::new(2, 2)));
     world.add_workload(not_parallel_workload);
     world.run_workload(not_parallel_workload).unwrap();
     // O : vel : 0
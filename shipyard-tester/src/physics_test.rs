@@ -0,0 +1,161 @@
+use crate::{Pos, Vel};
+use shipyard::*;
+use std::time::Duration;
+
+// 고정 시간 간격 한 번마다 Vel 에 더해지는 값 (x, y)
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Acceleration(pub(crate) i32, pub(crate) i32);
+
+// 프레임 시간을 모아 두었다가 고정 간격 단위로 나누어 준다.
+// Duration 은 정수 나노초이므로 프레임 시간을 어떻게 나누어 넣어도 같은 횟수가 나온다.
+#[derive(Unique, Debug)]
+pub(crate) struct FixedTimestep {
+    step: Duration,
+    accumulated: Duration,
+    max_steps: u32, // 한 프레임에 따라잡을 최대 횟수. 넘는 시간은 버린다.
+    total_steps: u64,
+}
+
+impl FixedTimestep {
+    pub(crate) fn new(step: Duration) -> FixedTimestep {
+        assert!(!step.is_zero(), "step must be positive");
+        FixedTimestep {
+            step,
+            accumulated: Duration::ZERO,
+            max_steps: 8,
+            total_steps: 0,
+        }
+    }
+
+    pub(crate) fn with_max_steps(mut self, max_steps: u32) -> FixedTimestep {
+        self.max_steps = max_steps;
+        self
+    }
+
+    // 게임 루프가 physics_workload 를 실행하기 전에 호출한다
+    pub(crate) fn advance(&mut self, frame_dt: Duration) {
+        self.accumulated += frame_dt;
+    }
+
+    pub(crate) fn total_steps(&self) -> u64 {
+        self.total_steps
+    }
+
+    // 남은 시간 / 간격. 렌더링 보간에 사용한다.
+    pub(crate) fn alpha(&self) -> f32 {
+        self.accumulated.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    fn consume_steps(&mut self) -> u32 {
+        let mut steps = 0;
+        while self.accumulated >= self.step && steps < self.max_steps {
+            self.accumulated -= self.step;
+            steps += 1;
+        }
+        if self.accumulated >= self.step {
+            // 처리가 밀리면 따라잡으려다 더 밀리므로 남은 시간을 버린다
+            let remainder = self.accumulated.as_nanos() % self.step.as_nanos();
+            self.accumulated = Duration::from_nanos(remainder as u64);
+        }
+        self.total_steps += steps as u64;
+        steps
+    }
+}
+
+// 반암시적 오일러: 속도를 먼저 갱신하고 새 속도로 위치를 옮긴다.
+// Pos 는 부호 없는 좌표이므로 0 아래로 가는 축은 0 에서 멈춘다 (속도는 그대로 남는다).
+fn physics_system(
+    mut timestep: UniqueViewMut<FixedTimestep>,
+    mut vm_pos: ViewMut<Pos>,
+    mut vm_vel: ViewMut<Vel>,
+    v_acc: View<Acceleration>,
+) {
+    for _ in 0..timestep.consume_steps() {
+        for (vel, acc) in (&mut vm_vel, &v_acc).iter() {
+            // 가속도가 0 이면 속도를 건드리지 않는다
+            if acc.0 != 0 || acc.1 != 0 {
+                vel.0 += acc.0;
+                vel.1 += acc.1;
+            }
+        }
+        for (mut pos, vel) in (&mut vm_pos, &vm_vel).iter() {
+            // 멈춘 엔티티는 수정으로 표시하지 않는다
+            if vel.0 != 0 || vel.1 != 0 {
+                pos.0 = pos.0.saturating_add_signed(vel.0);
+                pos.1 = pos.1.saturating_add_signed(vel.1);
+            }
+        }
+    }
+}
+
+pub(crate) fn physics_workload() -> Workload {
+    physics_system.into_workload()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::physics_test::*;
+
+    // 같은 총 시간을 서로 다른 프레임 시간으로 나누어 실행한다
+    fn simulate(frames: &[u64]) -> (Vec<(u32, u32)>, u64) {
+        let mut world = World::new();
+        world.add_unique(FixedTimestep::new(Duration::from_millis(10)).with_max_steps(100));
+        world.add_workload(physics_workload);
+
+        world.add_entity((Pos::new(100, 100), Vel::new(2, -1)));
+        world.add_entity((Pos::new(0, 0), Vel::new(0, 0), Acceleration(1, 2)));
+        world.add_entity(Pos::new(5, 5));
+
+        for &frame in frames {
+            world.run(|mut timestep: UniqueViewMut<FixedTimestep>| {
+                timestep.advance(Duration::from_millis(frame));
+            });
+            world.run_workload(physics_workload).unwrap();
+        }
+
+        world.run(|v_pos: View<Pos>, timestep: UniqueView<FixedTimestep>| {
+            (v_pos.iter().map(|pos| (pos.0, pos.1)).collect(), timestep.total_steps())
+        })
+    }
+
+    #[test]
+    fn fixed_timestep_test() {
+        let (steady, steps) = simulate(&[10; 10]);
+        assert_eq!(steps, 10);
+        // 등가속도: n 번 후 위치는 a * n(n+1)/2
+        assert_eq!(steady, vec![(120, 90), (55, 110), (5, 5)]);
+
+        // 프레임 시간이 달라도 결과는 같다
+        assert_eq!(simulate(&[3, 7, 25, 1, 4, 60]), (steady.clone(), steps));
+        assert_eq!(simulate(&[100]), (steady, steps));
+    }
+
+    #[test]
+    fn clamp_test() {
+        let mut world = World::new();
+        world.add_unique(FixedTimestep::new(Duration::from_millis(10)));
+        world.add_workload(physics_workload);
+        let entity = world.add_entity((Pos::new(3, 10), Vel::new(-2, -1)));
+
+        world.run(|mut timestep: UniqueViewMut<FixedTimestep>| {
+            timestep.advance(Duration::from_millis(30));
+        });
+        world.run_workload(physics_workload).unwrap();
+
+        // x 는 3 -> 1 -> 0 -> 0 으로 0 에서 멈추고 y 는 계속 움직인다
+        world.run(|v_pos: View<Pos>, v_vel: View<Vel>| {
+            assert_eq!((v_pos[entity].0, v_pos[entity].1), (0, 7));
+            assert_eq!((v_vel[entity].0, v_vel[entity].1), (-2, -1));
+        });
+    }
+
+    #[test]
+    fn max_steps_test() {
+        let mut timestep = FixedTimestep::new(Duration::from_millis(10)).with_max_steps(3);
+        timestep.advance(Duration::from_millis(75));
+        assert_eq!(timestep.consume_steps(), 3);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-4);
+        assert_eq!(timestep.consume_steps(), 0);
+    }
+}
@@ -0,0 +1,311 @@
+use crate::workload_report_test::{label_name, WorkloadReport};
+use serde_json::json;
+use shipyard::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+// 시스템 한 번 실행한 기록
+#[derive(Debug, Clone)]
+pub(crate) struct SystemSample {
+    pub(crate) name: String,
+    // add_workload 의 배치 정보에 없는 시스템이면 None
+    pub(crate) batch: Option<usize>,
+    pub(crate) start: Duration, // Profiler 생성 시점 기준
+    pub(crate) duration: Duration,
+}
+
+// 최근 window 프레임 동안의 시스템별 통계
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SystemSummary {
+    pub(crate) name: String,
+    pub(crate) batch: Option<usize>,
+    pub(crate) calls: usize,
+    pub(crate) total: Duration,
+    pub(crate) mean: Duration,
+    pub(crate) max: Duration,
+}
+
+struct Recording {
+    epoch: Instant,
+    window: usize,
+    frames: VecDeque<Vec<SystemSample>>,
+    current: Vec<SystemSample>,
+    // 측정할 워크로드 이름과 그 워크로드의 시스템별 배치 번호
+    workloads: HashSet<String>,
+    batches: HashMap<String, usize>,
+}
+
+// shipyard 의 tracing span 으로 run_workload 가 실제로 실행한 시스템의 시간을 잰다.
+// add_workload 로 추가한 워크로드만 기록하며, 워크로드 한 번 실행이 한 프레임이다.
+// layer 를 tracing 구독자에 등록해야 기록된다.
+#[derive(Unique, Clone)]
+pub(crate) struct Profiler {
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl Profiler {
+    pub(crate) fn new(window: usize) -> Profiler {
+        Profiler {
+            recording: Arc::new(Mutex::new(Recording {
+                epoch: Instant::now(),
+                window: window.max(1),
+                frames: VecDeque::new(),
+                current: Vec::new(),
+                workloads: HashSet::new(),
+                batches: HashMap::new(),
+            })),
+        }
+    }
+
+    fn recording(&self) -> MutexGuard<'_, Recording> {
+        self.recording.lock().unwrap()
+    }
+
+    pub(crate) fn layer(&self) -> ProfilerLayer {
+        ProfilerLayer {
+            recording: self.recording.clone(),
+        }
+    }
+
+    // workload 를 world 에 추가하고 측정 대상으로 삼는다. main_workload 같은 기존 워크로드도 그대로 넘기면 된다.
+    pub(crate) fn add_workload(&self, world: &World, workload: Workload) -> Result<(), error::AddWorkload> {
+        let info = workload.add_to_world(world)?;
+        let report = WorkloadReport::from_info(&info);
+
+        let mut recording = self.recording();
+        recording.workloads.insert(label_name(&*info.name));
+        for (index, batch) in report.batches.iter().enumerate() {
+            for system in &batch.systems {
+                recording.batches.insert(system.name.clone(), index);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn frames(&self) -> Vec<Vec<SystemSample>> {
+        self.recording().frames.iter().cloned().collect()
+    }
+
+    // 총 시간이 긴 순서
+    pub(crate) fn summary(&self) -> Vec<SystemSummary> {
+        let recording = self.recording();
+        let mut summaries: Vec<SystemSummary> = Vec::new();
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for sample in recording.frames.iter().flatten() {
+            let i = *index.entry(&sample.name).or_insert_with(|| {
+                summaries.push(SystemSummary {
+                    name: sample.name.clone(),
+                    batch: sample.batch,
+                    calls: 0,
+                    total: Duration::ZERO,
+                    mean: Duration::ZERO,
+                    max: Duration::ZERO,
+                });
+                summaries.len() - 1
+            });
+            let summary = &mut summaries[i];
+            summary.calls += 1;
+            summary.total += sample.duration;
+            summary.max = summary.max.max(sample.duration);
+        }
+        for summary in &mut summaries {
+            summary.mean = summary.total / summary.calls as u32;
+        }
+        summaries.sort_by(|a, b| b.total.cmp(&a.total).then(a.name.cmp(&b.name)));
+        summaries
+    }
+
+    // chrome://tracing 에서 열 수 있는 JSON. 배치마다 한 줄(tid)로 표시되고 배치를 모르는 시스템은 -1 줄에 모인다.
+    pub(crate) fn chrome_trace(&self) -> String {
+        let recording = self.recording();
+        let events = recording
+            .frames
+            .iter()
+            .flatten()
+            .map(|sample| {
+                json!({
+                    "name": sample.name,
+                    "cat": "system",
+                    "ph": "X",
+                    "ts": sample.start.as_secs_f64() * 1_000_000.0,
+                    "dur": sample.duration.as_secs_f64() * 1_000_000.0,
+                    "pid": 0,
+                    "tid": sample.batch.map_or(-1, |batch| batch as i64),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({ "traceEvents": events }).to_string()
+    }
+}
+
+// shipyard 의 "workload" / "system" span 을 받아 Profiler 에 기록한다
+pub(crate) struct ProfilerLayer {
+    recording: Arc<Mutex<Recording>>,
+}
+
+// span 에 붙여 두는 정보
+struct WorkloadSpan;
+
+struct SystemSpan {
+    name: String,
+    start: Option<Instant>,
+}
+
+impl<S> Layer<S> for ProfilerLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let mut visitor = NameVisitor(None);
+        attrs.record(&mut visitor);
+        let name = match visitor.0 {
+            Some(name) => name,
+            None => return,
+        };
+
+        match attrs.metadata().name() {
+            "workload" if self.recording.lock().unwrap().workloads.contains(&name) => {
+                span.extensions_mut().insert(WorkloadSpan);
+            }
+            "system" => {
+                // 측정하는 워크로드 안의 시스템만
+                if let Some(parent) = span.parent() {
+                    if parent.extensions().get::<WorkloadSpan>().is_some() {
+                        span.extensions_mut().insert(SystemSpan { name, start: None });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(system) = span.extensions_mut().get_mut::<SystemSpan>() {
+                system.start = Some(Instant::now());
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(id) {
+            Some(span) => span,
+            None => return,
+        };
+        let mut extensions = span.extensions_mut();
+        let system = match extensions.get_mut::<SystemSpan>() {
+            Some(system) => system,
+            None => return,
+        };
+        if let Some(start) = system.start.take() {
+            let duration = start.elapsed();
+            let mut recording = self.recording.lock().unwrap();
+            let sample = SystemSample {
+                batch: recording.batches.get(&system.name).copied(),
+                name: system.name.clone(),
+                start: start - recording.epoch,
+                duration,
+            };
+            recording.current.push(sample);
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(&id) {
+            Some(span) => span,
+            None => return,
+        };
+        let is_workload = span.extensions().get::<WorkloadSpan>().is_some();
+        if is_workload {
+            let mut recording = self.recording.lock().unwrap();
+            let samples = std::mem::take(&mut recording.current);
+            if recording.frames.len() == recording.window {
+                recording.frames.pop_front();
+            }
+            recording.frames.push_back(samples);
+        }
+    }
+}
+
+// span 의 name 필드. shipyard 는 라벨을 Debug 로 기록하므로 label_name 과 같은 모양으로 맞춘다.
+struct NameVisitor(Option<String>);
+
+impl Visit for NameVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "name" {
+            self.0 = Some(format!("{:?}", value).trim_matches('"').to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::profiler_test::*;
+    use tracing::Dispatch;
+    use tracing_subscriber::prelude::*;
+
+    fn profiled_workload() -> Workload {
+        Workload::new("profiled")
+            .with_system(read_only_system_1)
+            .with_system(read_only_system_2)
+            .with_system(read_write_system)
+    }
+
+    #[test]
+    fn profiler_test() {
+        let mut world = World::new();
+        let profiler = Profiler::new(3);
+        let dispatch = Dispatch::new(tracing_subscriber::registry().with(profiler.layer()));
+        profiler.add_workload(&world, profiled_workload()).unwrap();
+        world.add_unique(profiler);
+        world.add_entity((Vel::new(0, 0), Pos::new(0, 0)));
+
+        // 전역 구독자를 건드리지 않도록 이 테스트의 rayon 스레드에만 구독자를 등록한다
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .spawn_handler(|thread| {
+                let dispatch = dispatch.clone();
+                std::thread::spawn(move || tracing::dispatcher::with_default(&dispatch, || thread.run()));
+                Ok(())
+            })
+            .build()
+            .unwrap();
+        pool.install(|| {
+            for _ in 0..5 {
+                world.run_workload("profiled").unwrap();
+            }
+            // 워크로드 밖에서 실행한 시스템은 기록되지 않는다
+            world.run(read_write_system);
+        });
+
+        world.run(|profiler: UniqueView<Profiler>| {
+            assert_eq!(profiler.frames().len(), 3);
+            assert!(profiler.frames().iter().all(|frame| frame.len() == 3));
+
+            let summary = profiler.summary();
+            assert_eq!(summary.len(), 3);
+            assert!(summary.iter().all(|system| system.calls == 3 && system.max >= system.mean));
+            let batch_of = |name: &str| summary.iter().find(|system| system.name.ends_with(name)).unwrap().batch;
+            assert_eq!(batch_of("read_only_system_1"), Some(0));
+            assert_eq!(batch_of("read_only_system_2"), Some(0));
+            assert_eq!(batch_of("read_write_system"), Some(1));
+
+            let trace: serde_json::Value = serde_json::from_str(&profiler.chrome_trace()).unwrap();
+            let events = trace["traceEvents"].as_array().unwrap();
+            assert_eq!(events.len(), 9);
+            assert!(events.iter().all(|event| event["ph"] == "X"));
+        });
+    }
+}
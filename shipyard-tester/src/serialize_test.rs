@@ -0,0 +1,315 @@
+use crate::hierarchy::{Child, Hierarchy, HierarchyError, HierarchyIter, Parent};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shipyard::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+type ExportFn = fn(&AllStorages, EntityId) -> Option<Value>;
+type ImportFn = fn(Value) -> Result<Inserter, serde_json::Error>;
+// 역직렬화를 마친 컴포넌트를 엔티티에 추가한다
+type Inserter = Box<dyn FnOnce(&AllStorages, EntityId) -> Result<(), error::GetStorage>>;
+
+struct RegisteredComponent {
+    name: &'static str,
+    export: ExportFn,
+    import: ImportFn,
+}
+
+// 문서로 내보내고 가져올 컴포넌트 목록.
+// Parent/Child 는 문서의 중첩 구조로 표현되므로 등록하지 않는다.
+#[derive(Unique, Default)]
+pub(crate) struct ComponentRegistry {
+    entries: Vec<RegisteredComponent>,
+}
+
+impl ComponentRegistry {
+    pub(crate) fn register<T>(&mut self, name: &'static str)
+        where
+            T: Component + Serialize + DeserializeOwned + Send + Sync,
+    {
+        self.entries.push(RegisteredComponent {
+            name,
+            export: export_component::<T>,
+            import: import_component::<T>,
+        });
+    }
+}
+
+fn export_component<T>(all_storages: &AllStorages, id: EntityId) -> Option<Value>
+    where
+        T: Component + Serialize + Send + Sync,
+{
+    let view = all_storages.borrow::<View<T>>().ok()?;
+    let component = view.get(id).ok()?;
+    serde_json::to_value(component).ok()
+}
+
+fn import_component<T>(value: Value) -> Result<Inserter, serde_json::Error>
+    where
+        T: Component + DeserializeOwned + Send + Sync,
+{
+    let component: T = serde_json::from_value(value)?;
+    Ok(Box::new(move |all_storages: &AllStorages, id: EntityId| {
+        let (entities, mut view) = all_storages.borrow::<(EntitiesViewMut, ViewMut<T>)>()?;
+        entities.add_component(id, &mut view, component);
+        Ok(())
+    }))
+}
+
+// 엔티티 하나와 자식들. serde 문서이므로 JSON, RON 등 어떤 형식으로도 저장할 수 있다.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub(crate) struct EntityNode {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) components: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) children: Vec<EntityNode>,
+}
+
+#[derive(Debug)]
+pub(crate) enum ImportError {
+    UnknownComponent(String),
+    Component(serde_json::Error),
+    Hierarchy(HierarchyError),
+    Storage(error::GetStorage),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::UnknownComponent(name) => write!(f, "component `{}` is not registered", name),
+            ImportError::Component(err) => write!(f, "invalid component: {}", err),
+            ImportError::Hierarchy(err) => write!(f, "invalid hierarchy: {}", err),
+            ImportError::Storage(err) => write!(f, "storage unavailable: {:?}", err),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(err: serde_json::Error) -> ImportError {
+        ImportError::Component(err)
+    }
+}
+
+impl From<HierarchyError> for ImportError {
+    fn from(err: HierarchyError) -> ImportError {
+        ImportError::Hierarchy(err)
+    }
+}
+
+impl From<error::GetStorage> for ImportError {
+    fn from(err: error::GetStorage) -> ImportError {
+        ImportError::Storage(err)
+    }
+}
+
+// root 와 자손들을 등록된 컴포넌트와 함께 문서로 만든다
+pub(crate) fn export_subtree(all_storages: &AllStorages, root: EntityId) -> EntityNode {
+    let registry = all_storages.borrow::<UniqueView<ComponentRegistry>>();
+    let entries = registry.as_ref().map_or(&[][..], |registry| &registry.entries[..]);
+    let (parents, children) = all_storages
+        .borrow::<(View<Parent>, View<Child>)>()
+        .unwrap();
+
+    export_node(all_storages, entries, (&parents, &children), root)
+}
+
+fn export_node(
+    all_storages: &AllStorages,
+    entries: &[RegisteredComponent],
+    hierarchy: (&View<Parent>, &View<Child>),
+    id: EntityId,
+) -> EntityNode {
+    EntityNode {
+        components: entries
+            .iter()
+            .filter_map(|entry| (entry.export)(all_storages, id).map(|value| (entry.name.to_string(), value)))
+            .collect(),
+        children: hierarchy
+            .children(id)
+            .map(|child| export_node(all_storages, entries, hierarchy, child))
+            .collect(),
+    }
+}
+
+// 문서로부터 새 엔티티들을 만들고 parent 아래에 붙인다. 만들어진 루트를 반환한다.
+// 실패하면 만든 엔티티를 모두 삭제하므로 World 는 그대로다.
+pub(crate) fn import_subtree(
+    all_storages: &mut AllStorages,
+    node: &EntityNode,
+    parent: Option<EntityId>,
+) -> Result<EntityId, ImportError> {
+    let importers = all_storages
+        .borrow::<UniqueView<ComponentRegistry>>()
+        .map(|registry| {
+            registry
+                .entries
+                .iter()
+                .map(|entry| (entry.name, entry.import))
+                .collect::<HashMap<_, _>>()
+        })
+        .unwrap_or_default();
+
+    // 엔티티를 만들기 전에 문서 전체를 역직렬화해 둔다. 실패하면 World 는 그대로다.
+    let prepared = prepare_node(&importers, node)?;
+    let mut created = Vec::new();
+    match import_node(all_storages, prepared, parent, &mut created) {
+        Ok(id) => Ok(id),
+        Err(err) => {
+            if let Some(&root) = created.first() {
+                if let Ok(mut hierarchy) = all_storages.borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>)>() {
+                    hierarchy.detach(root);
+                }
+            }
+            for id in created {
+                all_storages.delete_entity(id);
+            }
+            Err(err)
+        }
+    }
+}
+
+// 컴포넌트를 모두 역직렬화한 노드
+struct PreparedNode {
+    components: Vec<Inserter>,
+    children: Vec<PreparedNode>,
+}
+
+fn prepare_node(importers: &HashMap<&'static str, ImportFn>, node: &EntityNode) -> Result<PreparedNode, ImportError> {
+    let components = node
+        .components
+        .iter()
+        .map(|(name, value)| {
+            let import = importers
+                .get(name.as_str())
+                .ok_or_else(|| ImportError::UnknownComponent(name.clone()))?;
+            Ok(import(value.clone())?)
+        })
+        .collect::<Result<Vec<_>, ImportError>>()?;
+    let children = node
+        .children
+        .iter()
+        .map(|child| prepare_node(importers, child))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(PreparedNode { components, children })
+}
+
+// 만든 엔티티는 만든 순서대로 created 에 넣는다
+fn import_node(
+    all_storages: &AllStorages,
+    node: PreparedNode,
+    parent: Option<EntityId>,
+    created: &mut Vec<EntityId>,
+) -> Result<EntityId, ImportError> {
+    let id = {
+        let mut hierarchy = all_storages.borrow::<(EntitiesViewMut, ViewMut<Parent>, ViewMut<Child>)>()?;
+        match parent {
+            Some(parent) => hierarchy.attach_new(parent)?,
+            None => hierarchy.0.add_entity((), ()),
+        }
+    };
+    created.push(id);
+
+    for insert in node.components {
+        insert(all_storages, id)?;
+    }
+    for child in node.children {
+        import_node(all_storages, child, Some(id), created)?;
+    }
+    Ok(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::hierarchy::Hierarchy;
+    use crate::serialize_test::*;
+
+    #[test]
+    fn round_trip_test() {
+        let world = World::new();
+        let mut registry = ComponentRegistry::default();
+        registry.register::<Pos>("Pos");
+        registry.register::<Vel>("Vel");
+        world.add_unique(registry);
+
+        let root = world.run(
+            |entities: EntitiesViewMut,
+             parents: ViewMut<Parent>,
+             children: ViewMut<Child>,
+             mut vm_pos: ViewMut<Pos>,
+             mut vm_vel: ViewMut<Vel>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root = hierarchy.0.add_entity(&mut vm_pos, Pos::new(1, 1));
+                let e1 = hierarchy.attach_new(root).unwrap();
+                let e2 = hierarchy.attach_new(root).unwrap();
+                let _e3 = hierarchy.attach_new(e2).unwrap();
+                hierarchy.0.add_component(e1, (&mut vm_pos, &mut vm_vel), (Pos::new(2, 2), Vel::new(3, 0)));
+                root
+            },
+        );
+
+        let json = world.run(|all_storages: AllStoragesViewMut| {
+            serde_json::to_string(&export_subtree(&all_storages, root)).unwrap()
+        });
+        assert_eq!(
+            json,
+            r#"{"components":{"Pos":[1,1]},"children":[{"components":{"Pos":[2,2],"Vel":[3,0]}},{"children":[{}]}]}"#
+        );
+
+        let node: EntityNode = serde_json::from_str(&json).unwrap();
+        let copy = world.run(|mut all_storages: AllStoragesViewMut| import_subtree(&mut all_storages, &node, None).unwrap());
+        assert_ne!(copy, root);
+
+        world.run(|parents: View<Parent>, children: View<Child>, v_pos: View<Pos>, v_vel: View<Vel>| {
+            let copies = (&parents, &children).descendants(copy).collect::<Vec<_>>();
+            assert_eq!(copies.len(), 3);
+            assert_eq!(v_pos[copy].0, 1);
+            assert_eq!(v_pos[copies[0]].0, 2);
+            assert_eq!(v_vel[copies[0]].0, 3);
+            assert!((&parents, &children).children(copies[1]).eq([copies[2]].iter().cloned()));
+        });
+
+        world.run(|all_storages: AllStoragesViewMut| {
+            assert_eq!(export_subtree(&all_storages, copy), node);
+        });
+    }
+
+    #[test]
+    fn unknown_component_test() {
+        let world = World::new();
+        let node: EntityNode = serde_json::from_str(r#"{"children":[{"components":{"Life":1}}]}"#).unwrap();
+
+        let result = world.run(|mut all_storages: AllStoragesViewMut| import_subtree(&mut all_storages, &node, None));
+        assert!(matches!(result, Err(ImportError::UnknownComponent(name)) if name == "Life"));
+        world.run(|entities: EntitiesView| {
+            assert_eq!(entities.iter().count(), 0);
+        });
+    }
+
+    #[test]
+    fn invalid_component_test() {
+        let world = World::new();
+        let mut registry = ComponentRegistry::default();
+        registry.register::<Pos>("Pos");
+        world.add_unique(registry);
+
+        // 루트는 올바르고 손자의 값만 잘못된 문서
+        let node: EntityNode = serde_json::from_str(
+            r#"{"components":{"Pos":[1,1]},"children":[{"children":[{"components":{"Pos":"oops"}}]}]}"#,
+        )
+        .unwrap();
+
+        let result = world.run(|mut all_storages: AllStoragesViewMut| import_subtree(&mut all_storages, &node, None));
+        assert!(matches!(result, Err(ImportError::Component(_))));
+        world.run(|entities: EntitiesView, v_pos: View<Pos>| {
+            assert_eq!(entities.iter().count(), 0);
+            assert_eq!(v_pos.len(), 0);
+        });
+    }
+}
@@ -0,0 +1,308 @@
+use crate::Pos;
+use shipyard::*;
+use std::collections::HashMap;
+
+type Point = (u32, u32);
+type Cell = (u32, u32);
+
+// 좌표 끝과 끝 사이의 거리 제곱은 u64 를 넘으므로 u128 로 계산한다
+fn distance_squared(a: Point, b: Point) -> u128 {
+    let dx = a.0.abs_diff(b.0) as u128;
+    let dy = a.1.abs_diff(b.1) as u128;
+    dx * dx + dy * dy
+}
+
+// Pos 를 균일한 격자로 나눈 공간 색인. spatial_index_system 이 Pos 변경 추적으로 갱신한다.
+#[derive(Unique)]
+pub(crate) struct SpatialGrid {
+    cell_size: u32,
+    cells: HashMap<Cell, Vec<EntityId>>,
+    positions: HashMap<EntityId, Point>,
+}
+
+impl SpatialGrid {
+    // 질의 반경과 비슷한 크기가 적당하다
+    pub(crate) fn new(cell_size: u32) -> SpatialGrid {
+        assert!(cell_size > 0, "cell size must be positive");
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn cell_of(&self, point: Point) -> Cell {
+        (point.0 / self.cell_size, point.1 / self.cell_size)
+    }
+
+    fn insert(&mut self, id: EntityId, point: Point) {
+        if let Some(old) = self.positions.insert(id, point) {
+            if self.cell_of(old) == self.cell_of(point) {
+                return;
+            }
+            self.remove_from_cell(id, old);
+        }
+        let cell = self.cell_of(point);
+        self.cells.entry(cell).or_default().push(id);
+    }
+
+    fn remove(&mut self, id: EntityId) {
+        if let Some(old) = self.positions.remove(&id) {
+            self.remove_from_cell(id, old);
+        }
+    }
+
+    fn remove_from_cell(&mut self, id: EntityId, point: Point) {
+        let cell = self.cell_of(point);
+        if let Some(ids) = self.cells.get_mut(&cell) {
+            ids.retain(|&other| other != id);
+            if ids.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    // min, max 를 포함하는 사각형 안의 엔티티. ID 순으로 정렬된다.
+    pub(crate) fn query_rect(&self, min: Point, max: Point) -> Vec<EntityId> {
+        let (min_cell, max_cell) = (self.cell_of(min), self.cell_of(max));
+        let mut found = Vec::new();
+        for cx in min_cell.0..=max_cell.0 {
+            for cy in min_cell.1..=max_cell.1 {
+                for &id in self.cells.get(&(cx, cy)).into_iter().flatten() {
+                    let (x, y) = self.positions[&id];
+                    if (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y) {
+                        found.push(id);
+                    }
+                }
+            }
+        }
+        found.sort();
+        found
+    }
+
+    // center 에서 radius 이내 (경계 포함). ID 순으로 정렬된다.
+    pub(crate) fn query_radius(&self, center: Point, radius: u32) -> Vec<EntityId> {
+        let min = (center.0.saturating_sub(radius), center.1.saturating_sub(radius));
+        let max = (center.0.saturating_add(radius), center.1.saturating_add(radius));
+        let radius_squared = radius as u128 * radius as u128;
+
+        let mut found = self.query_rect(min, max);
+        found.retain(|id| distance_squared(self.positions[id], center) <= radius_squared);
+        found
+    }
+
+    // 가까운 순서로 최대 k 개. 거리가 같으면 ID 순.
+    pub(crate) fn nearest_k(&self, center: Point, k: usize) -> Vec<EntityId> {
+        let mut candidates: Vec<(u128, EntityId)> = Vec::new();
+        let k = k.min(self.len());
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let (cx, cy) = self.cell_of(center);
+        let (cx, cy) = (cx as u64, cy as u64);
+        let (x, y) = (center.0 as u64, center.1 as u64);
+        let size = self.cell_size as u64;
+        let mut visited = 0;
+        // 중심 칸에서 ring 칸 떨어진 칸들을 차례로 살핀다
+        for ring in 0u64.. {
+            // 살핀 칸이 채워진 칸보다 많아지면 전체를 훑는 편이 빠르다
+            if visited > self.cells.len() {
+                return self.nearest_k_linear(center, k);
+            }
+            visited += for_each_ring_cell((cx, cy), ring, |cell| {
+                for &id in self.cells.get(&cell).into_iter().flatten() {
+                    candidates.push((distance_squared(self.positions[&id], center), id));
+                }
+            });
+
+            if candidates.len() >= k {
+                // 아직 살피지 않은 칸까지의 최소 거리. 0 아래쪽은 칸이 없다.
+                let mut bound = u64::MAX;
+                if cx > ring {
+                    bound = bound.min(x - (cx - ring) * size);
+                }
+                if cy > ring {
+                    bound = bound.min(y - (cy - ring) * size);
+                }
+                bound = bound.min((cx + ring + 1) * size - x).min((cy + ring + 1) * size - y);
+
+                candidates.sort();
+                // 거리가 같은 엔티티가 아직 살피지 않은 칸에 있을 수 있으므로 같을 때는 계속한다
+                if candidates[k - 1].0 < bound as u128 * bound as u128 || candidates.len() == self.len() {
+                    break;
+                }
+            }
+        }
+
+        candidates.sort();
+        candidates.into_iter().take(k).map(|(_, id)| id).collect()
+    }
+
+    fn nearest_k_linear(&self, center: Point, k: usize) -> Vec<EntityId> {
+        let mut all = self
+            .positions
+            .iter()
+            .map(|(&id, &point)| (distance_squared(point, center), id))
+            .collect::<Vec<_>>();
+        all.sort();
+        all.into_iter().take(k).map(|(_, id)| id).collect()
+    }
+}
+
+// center 칸에서 ring 칸 떨어진 테두리의 칸들만 방문하고 방문한 칸 수를 반환한다
+fn for_each_ring_cell(center: (u64, u64), ring: u64, mut f: impl FnMut(Cell)) -> usize {
+    let max = u32::MAX as u64;
+    let mut visited = 0;
+    let mut visit = |x: Option<u64>, y: Option<u64>| {
+        if let (Some(x), Some(y)) = (x, y) {
+            if x <= max && y <= max {
+                f((x as u32, y as u32));
+                visited += 1;
+            }
+        }
+    };
+
+    let (cx, cy) = center;
+    if ring == 0 {
+        visit(Some(cx), Some(cy));
+        return visited;
+    }
+    let (left, right) = (cx.checked_sub(ring), Some(cx + ring));
+    let (top, bottom) = (cy.checked_sub(ring), Some(cy + ring));
+    // 위아래 변은 모서리를 포함하고 좌우 변은 모서리를 뺀다
+    for x in cx.saturating_sub(ring)..=cx + ring {
+        visit(Some(x), top);
+        visit(Some(x), bottom);
+    }
+    for y in cy.saturating_sub(ring - 1)..=cy + ring - 1 {
+        visit(left, Some(y));
+        visit(right, Some(y));
+    }
+    visited
+}
+
+// 이 시스템이 마지막으로 실행된 뒤 Pos 가 추가/수정/제거된 엔티티만 색인에 반영한다.
+// 추적 기록은 지우지 않으므로 다른 시스템도 같은 기록을 읽을 수 있다.
+fn spatial_index_system(v_pos: View<Pos>, mut grid: UniqueViewMut<SpatialGrid>) {
+    for id in v_pos.removed_or_deleted() {
+        grid.remove(id);
+    }
+    for (id, pos) in v_pos.inserted_or_modified().iter().with_id() {
+        grid.insert(id, (pos.0, pos.1));
+    }
+}
+
+pub(crate) fn spatial_index_workload() -> Workload {
+    spatial_index_system.into_workload()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::spatial_index_test::*;
+
+    #[test]
+    fn spatial_index_test() {
+        let mut world = World::new();
+        world.add_unique(SpatialGrid::new(10));
+        world.add_workload(spatial_index_workload);
+
+        // 결정적인 의사 난수 좌표
+        let mut seed = 12345u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % 100
+        };
+        let points = (0..200).map(|_| (next(), next())).collect::<Vec<_>>();
+        let ids = world.run(|mut entities: EntitiesViewMut, mut vm_pos: ViewMut<Pos>| {
+            points
+                .iter()
+                .map(|&(x, y)| entities.add_entity(&mut vm_pos, Pos::new(x, y)))
+                .collect::<Vec<_>>()
+        });
+        world.run_workload(spatial_index_workload).unwrap();
+
+        // 전체를 훑은 결과와 비교
+        let brute_radius = |world: &World, center: Point, radius: u32| {
+            world.run(|v_pos: View<Pos>| {
+                let mut found = v_pos
+                    .iter()
+                    .with_id()
+                    .filter(|(_, pos)| distance_squared((pos.0, pos.1), center) <= radius as u128 * radius as u128)
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>();
+                found.sort();
+                found
+            })
+        };
+
+        world.run(|grid: UniqueView<SpatialGrid>, v_pos: View<Pos>| {
+            assert_eq!(grid.len(), 200);
+
+            let rect = grid.query_rect((20, 30), (45, 50));
+            assert!(rect.iter().all(|&id| (20..=45).contains(&v_pos[id].0) && (30..=50).contains(&v_pos[id].1)));
+            assert_eq!(
+                rect.len(),
+                v_pos.iter().filter(|pos| (20..=45).contains(&pos.0) && (30..=50).contains(&pos.1)).count()
+            );
+
+            let center = (50, 50);
+            let mut brute = v_pos
+                .iter()
+                .with_id()
+                .map(|(id, pos)| (distance_squared((pos.0, pos.1), center), id))
+                .collect::<Vec<_>>();
+            brute.sort();
+            let expected = brute.iter().take(7).map(|(_, id)| *id).collect::<Vec<_>>();
+            assert_eq!(grid.nearest_k(center, 7), expected);
+            assert_eq!(grid.nearest_k(center, 1000).len(), 200);
+        });
+        let near = world.run(|grid: UniqueView<SpatialGrid>| grid.query_radius((50, 50), 15));
+        assert_eq!(near, brute_radius(&world, (50, 50), 15));
+
+        // 이동과 삭제가 반영된다
+        world.run(|mut vm_pos: ViewMut<Pos>| {
+            let mut pos = (&mut vm_pos).get(ids[0]).unwrap();
+            pos.0 = 500;
+            pos.1 = 500;
+        });
+        world.delete_entity(ids[1]);
+        world.run_workload(spatial_index_workload).unwrap();
+
+        world.run(|grid: UniqueView<SpatialGrid>| {
+            assert_eq!(grid.len(), 199);
+            assert_eq!(grid.query_radius((500, 500), 0), vec![ids[0]]);
+            assert_eq!(grid.nearest_k((1000, 1000), 1), vec![ids[0]]);
+            assert!(!grid.query_rect((0, 0), (99, 99)).contains(&ids[1]));
+        });
+        let near = world.run(|grid: UniqueView<SpatialGrid>| grid.query_radius((50, 50), 15));
+        assert_eq!(near, brute_radius(&world, (50, 50), 15));
+
+        // 삭제 기록은 다른 시스템을 위해 남아 있고, 다시 실행해도 이미 반영한 변경은 건너뛴다
+        world.run(|v_pos: View<Pos>| assert!(v_pos.removed_or_deleted().any(|id| id == ids[1])));
+        world.run_workload(spatial_index_workload).unwrap();
+        world.run(|grid: UniqueView<SpatialGrid>| assert_eq!(grid.len(), 199));
+    }
+
+    #[test]
+    fn nearest_tie_test() {
+        let mut world = World::new();
+        let far = world.add_entity(());
+        let near = world.add_entity(());
+
+        // center 가 있는 칸의 near 와 옆 칸의 far 가 같은 거리에 있다
+        let mut grid = SpatialGrid::new(10);
+        grid.insert(far, (10, 5));
+        grid.insert(near, (8, 5));
+        assert_eq!(grid.nearest_k((9, 5), 1), vec![far]);
+        assert_eq!(grid.nearest_k((9, 5), 2), vec![far, near]);
+
+        // 멀리 떨어진 곳에서도 전체를 훑어 찾는다
+        assert_eq!(grid.nearest_k((u32::MAX, u32::MAX), 1), vec![far]);
+    }
+}
@@ -2,13 +2,13 @@ use shipyard::*;
 use crate::*;
 
 fn modify_system(mut view_life: ViewMut<Life>, mut view_composit: ViewMut<ComplexLife>) {
-    for (id, mut life) in (&mut view_life).iter().with_id() {
+    for mut life in (&mut view_life).iter() {
         if life.0 < 0 {
             life.0 = 0;
         }
     }
 
-    for (id, mut composit) in (&mut view_composit).iter().with_id() {
+    for mut composit in (&mut view_composit).iter() {
         let data = composit.data.get("item").unwrap();
         if *data < 0 {
             *composit.data.get_mut("item").unwrap() = 0;
@@ -19,7 +19,7 @@ fn modify_system(mut view_life: ViewMut<Life>, mut view_composit: ViewMut<Comple
 fn modified_system(mut view_life: ViewMut<Life>, mut view_composit: ViewMut<ComplexLife>) {
     let ids1: Vec<_> =
         view_life.modified().iter().ids().collect();
-    assert_eq!(ids1.iter().count(), 2);
+    assert_eq!(ids1.len(), 2);
     for id in ids1 {
         let life = view_life.remove(id).unwrap();
         assert_eq!(life.0, 0);
@@ -27,7 +27,7 @@ fn modified_system(mut view_life: ViewMut<Life>, mut view_composit: ViewMut<Comp
 
     let ids2: Vec<_> =
         view_composit.modified().iter().ids().collect();
-    assert_eq!(ids2.iter().count(), 2);
+    assert_eq!(ids2.len(), 2);
     for id in ids2 {
         let life = view_composit.remove(id).unwrap();
         let data = life.data.get("item").unwrap();
@@ -43,8 +43,8 @@ fn modified_workload() -> Workload {
     ).into_workload()
 }
 
-fn remove_system(mut view_life: View<Life>, mut view_dead: ViewMut<Dead>) {
-    for (id, mut life) in (&mut view_life).iter().with_id() {
+fn remove_system(view_life: View<Life>, mut view_dead: ViewMut<Dead>) {
+    for (id, life) in view_life.iter().with_id() {
         if life.0 > 0 {
             // deads.add_component_unchecked(id, Dead);
             view_dead.remove(id);
@@ -52,16 +52,12 @@ fn remove_system(mut view_life: View<Life>, mut view_dead: ViewMut<Dead>) {
     }
 }
 
-fn removed_system(mut view_life: View<Life>, mut view_dead: View<Dead>) {
+fn removed_system(view_life: View<Life>, view_dead: View<Dead>) {
     assert_eq!(view_dead.removed().count(), 3);
     for id in view_dead.removed() {
-        if let Ok(x) = view_dead.get(id) {
-            assert!(false);
-        }
+        assert!(view_dead.get(id).is_err());
         let life = view_life.get(id).unwrap();
-        if life.0 <= 0 {
-            assert!(false);
-        }
+        assert!(life.0 > 0);
     }
 }
 
@@ -73,6 +69,7 @@ fn remove_workload() -> Workload {
     ).into_workload()
 }
 
+#[cfg(test)]
 mod tests {
     use shipyard::*;
     use crate::tracking_test::*;
@@ -80,11 +77,11 @@ mod tests {
     #[test]
     fn modified_test() {
         let mut world = World::new();
-        world.add_entity(Life(3));
-        world.add_entity(Life(2));
-        world.add_entity(Life(-2));
-        world.add_entity(Life(1));
-        world.add_entity(Life(-1));
+        world.add_entity(Life::new(3));
+        world.add_entity(Life::new(2));
+        world.add_entity(Life::new(-2));
+        world.add_entity(Life::new(1));
+        world.add_entity(Life::new(-1));
 
         world.add_entity(ComplexLife::new(3));
         world.add_entity(ComplexLife::new(2));
@@ -104,11 +101,11 @@ mod tests {
     #[test]
     fn removed_test() {
         let mut world = World::new();
-        world.add_entity((Life(3), Dead));
-        world.add_entity((Life(2), Dead));
-        world.add_entity((Life(-2), Dead));
-        world.add_entity((Life(1), Dead));
-        world.add_entity((Life(0), Dead));
+        world.add_entity((Life::new(3), Dead));
+        world.add_entity((Life::new(2), Dead));
+        world.add_entity((Life::new(-2), Dead));
+        world.add_entity((Life::new(1), Dead));
+        world.add_entity((Life::new(0), Dead));
         world.add_workload(remove_workload);
         world.run_workload(remove_workload).unwrap();
         world.run(|view_dead: View<Dead>| {
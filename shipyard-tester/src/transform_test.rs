@@ -0,0 +1,265 @@
+use crate::hierarchy::{Child, HierarchyIter, Parent};
+use shipyard::*;
+use std::collections::HashSet;
+
+// 부모 기준 변환 (이동, 회전, 크기)
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+#[track(All)]
+pub(crate) struct LocalTransform {
+    pub(crate) translation: [f32; 2],
+    pub(crate) rotation: f32, // 라디안
+    pub(crate) scale: [f32; 2],
+}
+
+impl LocalTransform {
+    pub(crate) fn from_translation(x: f32, y: f32) -> LocalTransform {
+        LocalTransform {
+            translation: [x, y],
+            ..Default::default()
+        }
+    }
+
+    fn to_global(self) -> GlobalTransform {
+        let (sin, cos) = self.rotation.sin_cos();
+        GlobalTransform {
+            x_axis: [cos * self.scale[0], sin * self.scale[0]],
+            y_axis: [-sin * self.scale[1], cos * self.scale[1]],
+            translation: self.translation,
+        }
+    }
+}
+
+impl Default for LocalTransform {
+    fn default() -> LocalTransform {
+        LocalTransform {
+            translation: [0.0, 0.0],
+            rotation: 0.0,
+            scale: [1.0, 1.0],
+        }
+    }
+}
+
+// 월드 기준 변환. 전파 시스템이 계산하므로 직접 수정하지 않는다.
+// 회전과 비균등 크기가 섞이면 TRS 로 표현할 수 없어 행렬로 저장한다.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub(crate) struct GlobalTransform {
+    pub(crate) x_axis: [f32; 2],
+    pub(crate) y_axis: [f32; 2],
+    pub(crate) translation: [f32; 2],
+}
+
+impl GlobalTransform {
+    const IDENTITY: GlobalTransform = GlobalTransform {
+        x_axis: [1.0, 0.0],
+        y_axis: [0.0, 1.0],
+        translation: [0.0, 0.0],
+    };
+
+    fn transform_vector(&self, v: [f32; 2]) -> [f32; 2] {
+        [
+            self.x_axis[0] * v[0] + self.y_axis[0] * v[1],
+            self.x_axis[1] * v[0] + self.y_axis[1] * v[1],
+        ]
+    }
+
+    pub(crate) fn transform_point(&self, p: [f32; 2]) -> [f32; 2] {
+        let v = self.transform_vector(p);
+        [v[0] + self.translation[0], v[1] + self.translation[1]]
+    }
+
+    // self(부모) * local(자식)
+    fn mul(&self, local: &GlobalTransform) -> GlobalTransform {
+        GlobalTransform {
+            x_axis: self.transform_vector(local.x_axis),
+            y_axis: self.transform_vector(local.y_axis),
+            translation: self.transform_point(local.translation),
+        }
+    }
+}
+
+// 로컬 변환이 추가/수정/제거되었거나 부모가 바뀐 하위 트리만 다시 계산한다.
+// LocalTransform 이 없는 엔티티는 항등 변환으로 본다.
+fn transform_propagation_system(
+    entities: EntitiesView,
+    locals: View<LocalTransform>,
+    mut globals: ViewMut<GlobalTransform>,
+    parents: View<Parent>,
+    children: View<Child>,
+) {
+    let hierarchy = (&parents, &children);
+
+    let mut dirty = locals.inserted_or_modified().iter().ids().collect::<HashSet<_>>();
+    // LocalTransform 이 제거되어 항등 변환이 된 엔티티
+    dirty.extend(locals.removed().filter(|id| entities.is_alive(*id)));
+    // attach/detach 로 부모가 바뀐 엔티티
+    dirty.extend(
+        children
+            .inserted_or_modified()
+            .iter()
+            .ids()
+            .chain(children.removed_or_deleted())
+            .filter(|id| entities.is_alive(*id)),
+    );
+    // 아직 GlobalTransform 이 없는 엔티티도 계산 대상
+    dirty.extend(
+        locals
+            .iter()
+            .with_id()
+            .map(|(id, _)| id)
+            .filter(|id| !globals.contains(*id)),
+    );
+
+    // 조상이 dirty 이면 조상을 다시 계산할 때 함께 처리된다
+    let mut dirty_roots = dirty
+        .iter()
+        .copied()
+        .filter(|id| !hierarchy.ancestors(*id).any(|ancestor| dirty.contains(&ancestor)))
+        .collect::<Vec<_>>();
+    dirty_roots.sort();
+
+    let local_of = |id: EntityId| {
+        locals
+            .get(id)
+            .map_or(GlobalTransform::IDENTITY, |local| local.to_global())
+    };
+
+    for root in dirty_roots {
+        let parent_global = hierarchy
+            .ancestors(root)
+            .next()
+            .and_then(|parent| globals.get(parent).ok().copied())
+            .unwrap_or(GlobalTransform::IDENTITY);
+        entities.add_component(root, &mut globals, parent_global.mul(&local_of(root)));
+
+        // 전위 순회이므로 부모가 항상 먼저 계산된다
+        for id in hierarchy.descendants(root) {
+            let parent = hierarchy.ancestors(id).next().unwrap();
+            let parent_global = globals[parent];
+            entities.add_component(id, &mut globals, parent_global.mul(&local_of(id)));
+        }
+    }
+}
+
+fn transform_workload() -> Workload {
+    transform_propagation_system.into_workload()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hierarchy::Hierarchy;
+    use crate::transform_test::*;
+
+    fn assert_near(a: [f32; 2], b: [f32; 2]) {
+        assert!((a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn propagation_test() {
+        let world = World::new();
+        world.add_workload(transform_workload);
+
+        let (root, e1, e2, other) = world.run(
+            |entities: EntitiesViewMut,
+             parents: ViewMut<Parent>,
+             children: ViewMut<Child>,
+             mut locals: ViewMut<LocalTransform>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let root = hierarchy.0.add_entity(&mut locals, LocalTransform::from_translation(10.0, 0.0));
+                let e1 = hierarchy.attach_new(root).unwrap();
+                let e2 = hierarchy.attach_new(e1).unwrap();
+                let other = hierarchy.0.add_entity(&mut locals, LocalTransform::from_translation(-5.0, 0.0));
+
+                hierarchy.0.add_component(
+                    e1,
+                    &mut locals,
+                    LocalTransform {
+                        rotation: std::f32::consts::FRAC_PI_2,
+                        ..LocalTransform::from_translation(1.0, 0.0)
+                    },
+                );
+                hierarchy.0.add_component(e2, &mut locals, LocalTransform::from_translation(1.0, 0.0));
+                (root, e1, e2, other)
+            },
+        );
+
+        world.run_workload(transform_workload).unwrap();
+
+        world.run(|globals: View<GlobalTransform>| {
+            assert_near(globals[root].translation, [10.0, 0.0]);
+            assert_near(globals[e1].translation, [11.0, 0.0]);
+            // e1 이 90도 회전했으므로 (1, 0) 은 (0, 1) 이 된다
+            assert_near(globals[e2].translation, [11.0, 1.0]);
+            assert_near(globals[other].translation, [-5.0, 0.0]);
+        });
+
+        world.run(|mut locals: ViewMut<LocalTransform>| {
+            (&mut locals).get(root).unwrap().translation = [0.0, 5.0];
+        });
+        world.run_workload(transform_workload).unwrap();
+
+        world.run(|globals: View<GlobalTransform>| {
+            assert_near(globals[e1].translation, [1.0, 5.0]);
+            assert_near(globals[e2].translation, [1.0, 6.0]);
+            assert_near(globals[other].translation, [-5.0, 0.0]);
+        });
+
+        // 로컬 변환을 제거하면 항등 변환으로 다시 계산된다
+        world.run(|mut locals: ViewMut<LocalTransform>| {
+            locals.remove(e1);
+        });
+        world.run_workload(transform_workload).unwrap();
+
+        world.run(|globals: View<GlobalTransform>| {
+            assert_near(globals[e1].translation, [0.0, 5.0]);
+            assert_near(globals[e2].translation, [1.0, 5.0]);
+        });
+    }
+
+    #[test]
+    fn reparent_test() {
+        let world = World::new();
+        world.add_workload(transform_workload);
+
+        let (_, b, child, grandchild) = world.run(
+            |entities: EntitiesViewMut,
+             parents: ViewMut<Parent>,
+             children: ViewMut<Child>,
+             mut locals: ViewMut<LocalTransform>| {
+                let mut hierarchy = (entities, parents, children);
+
+                let a = hierarchy.0.add_entity(&mut locals, LocalTransform::from_translation(10.0, 0.0));
+                let b = hierarchy.0.add_entity(&mut locals, LocalTransform::from_translation(0.0, 20.0));
+                let child = hierarchy.attach_new(a).unwrap();
+                let grandchild = hierarchy.attach_new(child).unwrap();
+                hierarchy.0.add_component(child, &mut locals, LocalTransform::from_translation(1.0, 0.0));
+                hierarchy.0.add_component(grandchild, &mut locals, LocalTransform::from_translation(0.0, 1.0));
+                (a, b, child, grandchild)
+            },
+        );
+        world.run_workload(transform_workload).unwrap();
+        world.run(|globals: View<GlobalTransform>| {
+            assert_near(globals[grandchild].translation, [11.0, 1.0]);
+        });
+
+        // 로컬 변환은 그대로 두고 부모만 바꾼다
+        world.run(|entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+            (entities, parents, children).attach(child, b).unwrap();
+        });
+        world.run_workload(transform_workload).unwrap();
+        world.run(|globals: View<GlobalTransform>| {
+            assert_near(globals[child].translation, [1.0, 20.0]);
+            assert_near(globals[grandchild].translation, [1.0, 21.0]);
+        });
+
+        // 떼어내면 루트가 된다
+        world.run(|entities: EntitiesViewMut, parents: ViewMut<Parent>, children: ViewMut<Child>| {
+            (entities, parents, children).detach(child);
+        });
+        world.run_workload(transform_workload).unwrap();
+        world.run(|globals: View<GlobalTransform>| {
+            assert_near(globals[child].translation, [1.0, 0.0]);
+            assert_near(globals[grandchild].translation, [1.0, 1.0]);
+        });
+    }
+}
@@ -0,0 +1,249 @@
+use serde::Serialize;
+use shipyard::info::{Conflict, SystemInfo, TypeInfo, WorkloadInfo};
+use shipyard::*;
+use std::fmt;
+
+// 시스템 하나가 빌리는 저장소
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BorrowReport {
+    pub(crate) storage: String,
+    pub(crate) exclusive: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SystemReport {
+    pub(crate) name: String,
+    pub(crate) borrows: Vec<BorrowReport>,
+    // 이전 배치에 합류하지 못한 원인. 배치의 첫 시스템이 아니면 None.
+    pub(crate) conflict: Option<ConflictReport>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConflictKind {
+    // 다른 시스템과 같은 저장소를 빌린다
+    Borrow,
+    // 다른 시스템이 Send/Sync 가 아닌 저장소를 빌린다
+    OtherNotSendSync,
+    // 이 시스템이 Send/Sync 가 아닌 저장소를 빌린다
+    NotSendSync,
+}
+
+// 충돌한 저장소와 양쪽의 접근 방식
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConflictReport {
+    pub(crate) kind: ConflictKind,
+    pub(crate) storage: String,
+    pub(crate) exclusive: bool,
+    // NotSendSync 이면 None
+    pub(crate) other_system: Option<String>,
+    pub(crate) other_exclusive: Option<bool>,
+}
+
+impl ConflictReport {
+    fn new(conflict: &Conflict) -> ConflictReport {
+        match conflict {
+            Conflict::Borrow {
+                type_info,
+                other_system,
+                other_type_info,
+            } => {
+                // shipyard 0.6 은 항상 채우지만 비어 있으면 상대가 빌린 저장소로 본다
+                let type_info = type_info.as_ref().unwrap_or(other_type_info);
+                ConflictReport {
+                    kind: ConflictKind::Borrow,
+                    storage: type_info.name.to_string(),
+                    exclusive: is_exclusive(type_info),
+                    other_system: Some(label_name(&*other_system.name)),
+                    other_exclusive: Some(is_exclusive(other_type_info)),
+                }
+            }
+            Conflict::OtherNotSendSync { system, type_info } => ConflictReport {
+                kind: ConflictKind::OtherNotSendSync,
+                storage: type_info.name.to_string(),
+                exclusive: is_exclusive(type_info),
+                other_system: Some(label_name(&*system.name)),
+                other_exclusive: None,
+            },
+            Conflict::NotSendSync(type_info) => ConflictReport {
+                kind: ConflictKind::NotSendSync,
+                storage: type_info.name.to_string(),
+                exclusive: is_exclusive(type_info),
+                other_system: None,
+                other_exclusive: None,
+            },
+        }
+    }
+}
+
+// &mut Vel 이 other 의 &Vel 과 충돌 (Borrow)
+impl fmt::Display for ConflictReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", borrow_prefix(self.exclusive), self.storage)?;
+        if let Some(other_system) = &self.other_system {
+            write!(f, " vs {}", other_system)?;
+            if let Some(other_exclusive) = self.other_exclusive {
+                write!(f, " {}{}", borrow_prefix(other_exclusive), self.storage)?;
+            }
+        }
+        write!(f, " ({:?})", self.kind)
+    }
+}
+
+fn is_exclusive(type_info: &TypeInfo) -> bool {
+    type_info.mutability == Mutability::Exclusive
+}
+
+// 라벨의 Debug 출력. 문자열 라벨은 따옴표를 뗀다.
+pub(crate) fn label_name(label: &dyn Label) -> String {
+    format!("{:?}", label).trim_matches('"').to_string()
+}
+
+fn borrow_prefix(exclusive: bool) -> &'static str {
+    if exclusive {
+        "&mut "
+    } else {
+        "&"
+    }
+}
+
+// 같은 배치의 시스템들은 병렬로 실행될 수 있다
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BatchReport {
+    pub(crate) systems: Vec<SystemReport>,
+}
+
+// shipyard 가 워크로드를 나눈 배치와 그 이유
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct WorkloadReport {
+    pub(crate) batches: Vec<BatchReport>,
+}
+
+impl WorkloadReport {
+    pub(crate) fn from_info(info: &WorkloadInfo) -> WorkloadReport {
+        WorkloadReport {
+            batches: info
+                .batch_info
+                .iter()
+                .map(|batch| BatchReport {
+                    // 메인 스레드에서만 실행되는 시스템이 먼저 온다
+                    systems: batch
+                        .systems
+                        .0
+                        .iter()
+                        .chain(&batch.systems.1)
+                        .map(system_report)
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+fn system_report(system: &SystemInfo) -> SystemReport {
+    SystemReport {
+        name: label_name(&*system.name),
+        borrows: system
+            .borrow
+            .iter()
+            .map(|type_info| BorrowReport {
+                storage: type_info.name.to_string(),
+                exclusive: is_exclusive(type_info),
+            })
+            .collect(),
+        conflict: system.conflict.as_ref().map(ConflictReport::new),
+    }
+}
+
+// 빈 World 에 추가해 보고 shipyard 가 만든 배치 정보를 가져온다
+pub(crate) fn workload_report(workload: Workload) -> Result<WorkloadReport, error::AddWorkload> {
+    let world = World::new();
+    let info = workload.add_to_world(&world)?;
+    Ok(WorkloadReport::from_info(&info))
+}
+
+// batch | system | borrows | conflict 표
+impl fmt::Display for WorkloadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = ["batch", "system", "borrows", "conflict"];
+        let mut rows = Vec::new();
+        for (index, batch) in self.batches.iter().enumerate() {
+            for system in &batch.systems {
+                let borrows = system
+                    .borrows
+                    .iter()
+                    .map(|borrow| format!("{}{}", borrow_prefix(borrow.exclusive), borrow.storage))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                rows.push([
+                    index.to_string(),
+                    system.name.clone(),
+                    borrows,
+                    system.conflict.as_ref().map(ToString::to_string).unwrap_or_default(),
+                ]);
+            }
+        }
+
+        let mut widths = header.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let mut write_row = |cells: [&str; 4]| {
+            let line = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join(" | ");
+            writeln!(f, "{}", line.trim_end())
+        };
+        write_row(header)?;
+        for row in &rows {
+            write_row([&row[0], &row[1], &row[2], &row[3]])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::workload_report_test::*;
+
+    #[test]
+    fn report_test() {
+        // 읽기만 하는 두 시스템은 한 배치
+        let report = workload_report(parallel_workload()).unwrap();
+        assert_eq!(report.batches.len(), 1);
+        assert_eq!(report.batches[0].systems.len(), 2);
+        assert!(report.batches[0].systems.iter().all(|system| system.conflict.is_none()));
+
+        // ViewMut<Vel> 때문에 두 번째 시스템은 새 배치로 간다
+        let report = workload_report(not_parallel_workload()).unwrap();
+        assert_eq!(report.batches.len(), 2);
+        let writer = &report.batches[1].systems[0];
+        assert!(writer.name.contains("read_write_system"));
+        assert!(writer.borrows.iter().any(|borrow| borrow.exclusive && borrow.storage.contains("Vel")));
+        let conflict = writer.conflict.as_ref().unwrap();
+        assert_eq!(conflict.kind, ConflictKind::Borrow);
+        assert!(conflict.storage.contains("Vel"));
+        assert!(conflict.exclusive);
+        assert_eq!(conflict.other_exclusive, Some(false));
+        assert!(conflict.other_system.is_some());
+
+        let table = report.to_string();
+        assert_eq!(table.lines().count(), 3);
+        assert!(table.lines().next().unwrap().starts_with("batch | system"));
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["batches"].as_array().unwrap().len(), 2);
+        assert_eq!(json["batches"][1]["systems"][0]["borrows"][0]["exclusive"], true);
+        assert_eq!(json["batches"][1]["systems"][0]["conflict"]["kind"], "Borrow");
+    }
+}
@@ -27,17 +27,17 @@ fn main_workload() -> Workload {
     (decrease_vel_system, filter_vel_workload).into_workload()
 }
 
+#[cfg(test)]
 mod tests {
     use shipyard::*;
-    use crate::*;
     use crate::workload_test::*;
 
     #[test]
     fn get_and_modify_component_test() {
         let mut world = World::new();
-        world.add_entity(Vel(3));
-        world.add_entity(Vel(2));
-        world.add_entity(Vel(1));
+        world.add_entity(Vel(3, 0));
+        world.add_entity(Vel(2, 0));
+        world.add_entity(Vel(1, 0));
         world.add_workload(main_workload);
 
         world.run_workload(main_workload).unwrap();